@@ -0,0 +1,134 @@
+use serde::{Serialize, Deserialize};
+use crate::constraints::Constraints;
+use crate::solver::Person;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryBound {
+    pub choice_score: i32,
+    /// No assignment of this category can score more than this
+    pub upper_bound: i32,
+    /// `upper_bound - choice_score`
+    pub gap: i32,
+}
+
+impl CategoryBound {
+    pub fn new(choice_score: i32, upper_bound: i32) -> CategoryBound {
+        CategoryBound {
+            choice_score,
+            upper_bound,
+            gap: (upper_bound - choice_score).max(0),
+        }
+    }
+}
+
+/// Percentage of the bound not yet reached, 0 when the bound is met
+pub fn gap_percent(choice_score: i32, upper_bound: i32) -> f64 {
+    if upper_bound <= 0 {
+        return 0.0;
+    }
+    ((upper_bound - choice_score).max(0) as f64 / upper_bound as f64) * 100.0
+}
+
+/// Upper bound on the choice score of one pool, whatever the assignment.
+///
+/// Takes the smallest of four bounds that each ignore some of the problem:
+/// - everyone gets all their reachable choices, up to `room_size - 1`
+/// - everyone is chosen by all reachable choosers, up to `room_size - 1`
+/// - per person, the best `room_size - 1` roommates counting a mutual pair as 2 and a
+///   one-way choice as 1, halved since each pair is counted from both ends
+/// - every room is full of people who all chose each other
+///
+/// "Reachable" means same pool, category-compatible and not avoided either way.
+pub fn choice_score_upper_bound(pool_people: &[Person], sizes: &[usize], constraints: &Constraints) -> i32 {
+    let n = pool_people.len();
+    let room_size = sizes.iter().copied().max().unwrap_or(0);
+    if n == 0 || room_size < 2 {
+        return 0;
+    }
+    let slots = room_size - 1;
+
+    let can_share = |a: &Person, b: &Person| {
+        constraints.compatible(&a.category, &b.category)
+            && !a.avoids.contains(&b.id)
+            && !b.avoids.contains(&a.id)
+    };
+
+    // chooses[i][j]: i chose j and the two could share a room
+    let chooses: Vec<Vec<bool>> = pool_people.iter()
+        .map(|a| pool_people.iter()
+            .map(|b| a.id != b.id && a.choices.contains(&b.id) && can_share(a, b))
+            .collect())
+        .collect();
+
+    let out_bound: usize = (0..n)
+        .map(|i| (0..n).filter(|&j| chooses[i][j]).count().min(slots))
+        .sum();
+
+    let in_bound: usize = (0..n)
+        .map(|j| (0..n).filter(|&i| chooses[i][j]).count().min(slots))
+        .sum();
+
+    let pair_bound: usize = (0..n)
+        .map(|i| {
+            let mut weights: Vec<usize> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| chooses[i][j] as usize + chooses[j][i] as usize)
+                .filter(|&w| w > 0)
+                .collect();
+            weights.sort_by(|a, b| b.cmp(a));
+            weights.iter().take(slots).sum::<usize>()
+        })
+        .sum::<usize>() / 2;
+
+    let capacity_bound: usize = sizes.iter().map(|&s| s * s.saturating_sub(1)).sum();
+
+    out_bound.min(in_bound).min(pair_bound).min(capacity_bound) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person};
+
+    #[test]
+    fn gap_is_the_share_of_the_bound_not_reached() {
+        assert_eq!(gap_percent(8, 10), 20.0);
+        assert_eq!(gap_percent(10, 10), 0.0);
+        assert_eq!(gap_percent(12, 10), 0.0);
+        assert_eq!(gap_percent(0, 0), 0.0);
+    }
+
+    #[test]
+    fn gap_never_goes_negative() {
+        assert_eq!(CategoryBound::new(12, 10).gap, 0);
+        assert_eq!(CategoryBound::new(7, 10).gap, 3);
+    }
+
+    #[test]
+    fn everyone_choosing_everyone_is_bounded_by_room_capacity() {
+        let ids = ["b1", "b2", "b3", "b4"];
+        let people: Vec<Person> = ids.iter()
+            .map(|id| person(id, "Boys", &ids.iter().filter(|other| *other != id).copied().collect::<Vec<_>>()))
+            .collect();
+        let constraints = constraints(&people, "", 2);
+
+        assert_eq!(choice_score_upper_bound(&people, &[2, 2], &constraints), 4);
+    }
+
+    #[test]
+    fn choices_that_can_never_share_a_room_do_not_count() {
+        let mut people = vec![person("b1", "Boys", &["b2"]), person("b2", "Boys", &["b1"])];
+        people[1].avoids.push("b1".to_string());
+        let constraints = constraints(&people, "", 2);
+
+        assert_eq!(choice_score_upper_bound(&people, &[2], &constraints), 0);
+    }
+
+    #[test]
+    fn single_rooms_satisfy_no_one() {
+        let people = vec![person("b1", "Boys", &["b2"]), person("b2", "Boys", &["b1"])];
+        let constraints = constraints(&people, "", 1);
+
+        assert_eq!(choice_score_upper_bound(&people, &[1, 1], &constraints), 0);
+    }
+}
@@ -0,0 +1,810 @@
+use serde::{Serialize, Deserialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::solver::{Person, Room, SolveOptions};
+
+// Room rules over person attributes, one per line:
+//
+//   Boys: same School <= 2                 no more than 2 from the same school per room
+//   *: spread Age <= 2                     max age spread of 2 years in a room
+//   Girls: count Returning = Yes >= 1      at least one returning camper per room
+//   *: same Grade <= 3 soft 50             as a penalty of 50 per extra person instead of a hard rule
+//
+// The part before the colon is the room category, or * for every category.
+// Hard rules are checked while placing people; soft rules cost points in the objective.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleKind {
+    /// At most `max` people in a room sharing the same value
+    MaxSame { attribute: String, max: usize },
+    /// Numeric values in a room differ by at most `max`
+    MaxSpread { attribute: String, max: f64 },
+    /// At least `min` people in a room with `attribute == value`
+    MinCount { attribute: String, value: String, min: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength {
+    Hard,
+    /// Penalty per unit of violation
+    Soft(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeRule {
+    /// `None` applies the rule to every category
+    pub category: Option<String>,
+    pub kind: RuleKind,
+    pub strength: Strength,
+    /// Original line, for reporting
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleViolation {
+    pub category: String,
+    /// Index of the room within its category
+    pub room: usize,
+    pub rule: String,
+    /// How far over (or under) the limit the room is
+    pub amount: usize,
+    pub penalty: i32,
+}
+
+/// Someone sharing a room with a person on their PreferNot list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftAvoidViolation {
+    pub category: String,
+    /// Index of the room within its category
+    pub room: usize,
+    pub person: String,
+    pub avoided: String,
+    pub penalty: i32,
+}
+
+/// How people with `Role = Leader` are spread across rooms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderPolicy {
+    /// Leaders are placed like everyone else
+    #[default]
+    None,
+    /// Every room gets exactly one leader; the number of rooms follows the number of leaders
+    OnePerRoom,
+    /// Leaders are placed first, one per room, before anyone else is assigned
+    FillFirst,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    pub rules: Vec<AttributeRule>,
+    /// Cost of each PreferNot entry that ends up in the same room
+    pub prefer_not_penalty: i32,
+    pub leader_policy: LeaderPolicy,
+    /// Pairs of different categories allowed to share a room, stored both ways round
+    pub compatible_pairs: HashSet<(String, String)>,
+    /// Categories allowed to share a room with anyone (`Staff, *`)
+    pub compatible_with_all: HashSet<String>,
+    /// Category -> label of the room pool it draws from ("Grade 9 + Grade 10")
+    pub pools: HashMap<String, String>,
+    /// Person ID -> pool label, for people whose category may join any room and so is
+    /// spread across pools
+    pub person_pools: HashMap<String, String>,
+    /// Pool label -> every category with people in it
+    pub pool_categories: HashMap<String, BTreeSet<String>>,
+    /// Most people allowed in one room; 0 when not known
+    pub max_room_size: usize,
+}
+
+const DEFAULT_SOFT_WEIGHT: i32 = 10;
+
+impl Constraints {
+    pub fn parse(text: &str, people: &[Person]) -> Result<Constraints, String> {
+        let mut rules = Vec::new();
+
+        for (line_idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let rule = parse_rule(line, people)
+                .map_err(|e| format!("Room rules line {}: {} ('{}')", line_idx + 1, e, line))?;
+            rules.push(rule);
+        }
+
+        Ok(Constraints { rules, ..Default::default() })
+    }
+
+    /// Everything configured in the UI that shapes which rooms are valid or preferred
+    pub fn from_options(options: &SolveOptions, people: &[Person]) -> Result<Constraints, String> {
+        let mut constraints = Constraints::parse(&options.room_rules, people)?;
+        constraints.prefer_not_penalty = options.prefer_not_penalty.max(0);
+        constraints.leader_policy = options.leader_policy;
+        constraints.parse_compatibility(&options.category_compatibility, people)?;
+        Ok(constraints)
+    }
+
+    /// Reads category compatibility, one group per line:
+    ///
+    ///   Grade 9, Grade 10      these two categories may share rooms
+    ///   Staff, *               staff may join any room
+    ///
+    /// Rooms are sized per pool, so everyone in a pool must be able to share a room.
+    /// Categories linked by lines without `*` form one pool, and a chain like
+    /// `Grade 9, Grade 10` and `Grade 10, Grade 11` is an error unless Grade 9 and
+    /// Grade 11 may share too. People in a `*` category are spread across the pools, so
+    /// `Staff, *` doesn't let Boys and Girls mix.
+    fn parse_compatibility(&mut self, text: &str, people: &[Person]) -> Result<(), String> {
+        let categories: BTreeSet<&str> = people.iter().map(|p| p.category.as_str()).collect();
+
+        for (line_idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let names: Vec<&str> = line.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()).collect();
+            let wildcard = names.contains(&"*");
+            let group: Vec<&str> = names.into_iter().filter(|n| *n != "*").collect();
+
+            for name in &group {
+                if !categories.contains(name) {
+                    return Err(format!("Category compatibility line {}: no one has category '{}'",
+                                       line_idx + 1, name));
+                }
+            }
+            if group.is_empty() || (group.len() == 1 && !wildcard) {
+                return Err(format!("Category compatibility line {}: list two or more categories, or a category and *",
+                                   line_idx + 1));
+            }
+
+            for a in &group {
+                if wildcard {
+                    self.compatible_with_all.insert(a.to_string());
+                }
+                for b in &group {
+                    if a != b {
+                        self.compatible_pairs.insert((a.to_string(), b.to_string()));
+                    }
+                }
+            }
+        }
+
+        let wildcards: BTreeSet<&str> = categories.iter()
+            .copied()
+            .filter(|c| self.compatible_with_all.contains(*c))
+            .collect();
+
+        // Group the other categories into pools: connected components of the compatibility relation
+        let mut pool_members: Vec<BTreeSet<&str>> = categories.iter()
+            .filter(|c| !wildcards.contains(*c))
+            .map(|c| BTreeSet::from([*c]))
+            .collect();
+        loop {
+            let mut merged = false;
+            'search: for i in 0..pool_members.len() {
+                for j in (i + 1)..pool_members.len() {
+                    let linked = pool_members[i].iter()
+                        .any(|a| pool_members[j].iter().any(|b| self.compatible(a, b)));
+                    if linked {
+                        let other = pool_members.remove(j);
+                        pool_members[i].extend(other);
+                        merged = true;
+                        break 'search;
+                    }
+                }
+            }
+            if !merged {
+                break;
+            }
+        }
+
+        for members in &pool_members {
+            for a in members {
+                if let Some(b) = members.iter().find(|b| !self.compatible(a, b)) {
+                    return Err(format!("Category compatibility: {} and {} would share a pool of rooms but may not \
+                                        share a room; put them on one line if they may", a, b));
+                }
+            }
+        }
+
+        // With no other pools, `*` categories form one pool of their own
+        let spread_out = !pool_members.is_empty();
+        let spread = if spread_out {
+            spread_wildcards(&mut pool_members, &wildcards, people)
+        } else {
+            pool_members.push(wildcards.clone());
+            Vec::new()
+        };
+
+        let labels: Vec<String> = pool_members.iter()
+            .map(|members| members.iter().copied().collect::<Vec<_>>().join(" + "))
+            .collect();
+        for (members, label) in pool_members.iter().zip(&labels) {
+            for category in members {
+                if !(spread_out && wildcards.contains(category)) {
+                    self.pools.insert(category.to_string(), label.clone());
+                }
+            }
+            self.pool_categories.insert(label.clone(), members.iter().map(|c| c.to_string()).collect());
+        }
+        for (id, pool) in spread {
+            self.person_pools.insert(id.to_string(), labels[pool].clone());
+        }
+
+        Ok(())
+    }
+
+    /// May people from these two categories share a room?
+    pub fn compatible(&self, a: &str, b: &str) -> bool {
+        a == b
+            || self.compatible_with_all.contains(a)
+            || self.compatible_with_all.contains(b)
+            || self.compatible_pairs.contains(&(a.to_string(), b.to_string()))
+    }
+
+    /// The room category a person's category is placed in; just the category unless mixing is allowed
+    pub fn pool_of<'a>(&'a self, category: &'a str) -> &'a str {
+        self.pools.get(category).map(|label| label.as_str()).unwrap_or(category)
+    }
+
+    /// The room pool a person is placed in; people in a `*` category each have their own
+    pub fn pool_for<'a>(&'a self, person: &'a Person) -> &'a str {
+        self.person_pools.get(&person.id)
+            .map(|label| label.as_str())
+            .unwrap_or_else(|| self.pool_of(&person.category))
+    }
+
+    /// Rules for a room; a rule written for one category covers every pool it has people in
+    fn rules_for<'a>(&'a self, room_category: &'a str) -> impl Iterator<Item = &'a AttributeRule> + 'a {
+        self.rules.iter().filter(move |rule| match &rule.category {
+            Some(c) => self.pool_categories.get(room_category)
+                .map_or(c == room_category, |categories| categories.contains(c)),
+            None => true,
+        })
+    }
+
+    /// Hard rules only: would the room still be valid (and still completable) with `person` added?
+    pub fn allows(&self, person: &Person, room: &Room, all_people: &[Person]) -> bool {
+        let members: Vec<&Person> = room.members.iter()
+            .filter_map(|id| all_people.iter().find(|p| &p.id == id))
+            .collect();
+
+        if members.iter().any(|m| !self.compatible(&person.category, &m.category)) {
+            return false;
+        }
+
+        if self.leader_policy == LeaderPolicy::OnePerRoom {
+            let has_leader = members.iter().any(|m| is_leader(m));
+            if is_leader(person) {
+                if has_leader {
+                    return false;
+                }
+            } else if !has_leader && room.members.len() + 1 >= room.max_size {
+                // Keep the last slot for a leader
+                return false;
+            }
+        }
+
+        for rule in self.rules_for(&room.category) {
+            if rule.strength != Strength::Hard {
+                continue;
+            }
+            let members: Vec<&Person> = members.iter().copied().filter(|m| rule.covers(m)).collect();
+            let person_counts = rule.covers(person);
+
+            match &rule.kind {
+                RuleKind::MaxSame { .. } | RuleKind::MaxSpread { .. } if !person_counts => {}
+                RuleKind::MaxSame { attribute, max } => {
+                    if let Some(value) = attribute_value(person, attribute) {
+                        let same = members.iter()
+                            .filter(|m| attribute_value(m, attribute) == Some(value))
+                            .count();
+                        if same + 1 > *max {
+                            return false;
+                        }
+                    }
+                }
+                RuleKind::MaxSpread { attribute, max } => {
+                    if let Some(value) = numeric_value(person, attribute) {
+                        for member in &members {
+                            if let Some(other) = numeric_value(member, attribute) {
+                                if (value - other).abs() > *max {
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                }
+                RuleKind::MinCount { attribute, value, min } => {
+                    // Refuse the last slots if they are needed for people who match
+                    let have = members.iter()
+                        .filter(|m| matches_value(m, attribute, value))
+                        .count()
+                        + (person_counts && matches_value(person, attribute, value)) as usize;
+                    let slots_left = room.max_size.saturating_sub(room.members.len() + 1);
+                    if have + slots_left < *min {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Every rule a finished room breaks; hard rules only show up here for hand-made assignments
+    pub fn violations(&self, solution: &[Room], all_people: &[Person]) -> Vec<RuleViolation> {
+        self.rule_violations(solution, all_people, true)
+    }
+
+    /// Hard rules (and one leader per room) that finished rooms break
+    pub fn hard_violations(&self, solution: &[Room], all_people: &[Person]) -> Vec<RuleViolation> {
+        self.rule_violations(solution, all_people, false)
+    }
+
+    fn rule_violations(&self, solution: &[Room], all_people: &[Person], include_soft: bool) -> Vec<RuleViolation> {
+        let mut violations = Vec::new();
+        let mut room_numbers: HashMap<&str, usize> = HashMap::new();
+
+        for room in solution {
+            let room_idx = room_numbers.entry(room.category.as_str()).or_insert(0);
+            let members: Vec<&Person> = room.members.iter()
+                .filter_map(|id| all_people.iter().find(|p| &p.id == id))
+                .collect();
+
+            if self.leader_policy == LeaderPolicy::OnePerRoom {
+                let leaders = members.iter().filter(|m| is_leader(m)).count();
+                if leaders != 1 {
+                    violations.push(RuleViolation {
+                        category: room.category.clone(),
+                        room: *room_idx,
+                        rule: "Exactly one leader per room".to_string(),
+                        amount: leaders.abs_diff(1),
+                        penalty: 0,
+                    });
+                }
+            }
+
+            for rule in self.rules_for(&room.category) {
+                if !include_soft && rule.strength != Strength::Hard {
+                    continue;
+                }
+                let covered: Vec<&Person> = members.iter().copied().filter(|m| rule.covers(m)).collect();
+                let amount = rule.kind.excess(&covered);
+                if amount > 0 {
+                    let penalty = match rule.strength {
+                        Strength::Soft(weight) => weight * amount as i32,
+                        Strength::Hard => 0,
+                    };
+                    violations.push(RuleViolation {
+                        category: room.category.clone(),
+                        room: *room_idx,
+                        rule: rule.text.clone(),
+                        amount,
+                        penalty,
+                    });
+                }
+            }
+
+            *room_idx += 1;
+        }
+
+        violations
+    }
+
+    /// Every PreferNot entry that shares a room with the person who listed it
+    pub fn soft_avoid_violations(&self, solution: &[Room], all_people: &[Person]) -> Vec<SoftAvoidViolation> {
+        let mut violations = Vec::new();
+        let mut room_numbers: HashMap<&str, usize> = HashMap::new();
+
+        for room in solution {
+            let room_idx = room_numbers.entry(room.category.as_str()).or_insert(0);
+
+            for member_id in &room.members {
+                if let Some(member) = all_people.iter().find(|p| &p.id == member_id) {
+                    for other in &member.prefer_not {
+                        if room.members.contains(other) {
+                            violations.push(SoftAvoidViolation {
+                                category: room.category.clone(),
+                                room: *room_idx,
+                                person: member.id.clone(),
+                                avoided: other.clone(),
+                                penalty: self.prefer_not_penalty,
+                            });
+                        }
+                    }
+                }
+            }
+
+            *room_idx += 1;
+        }
+
+        violations
+    }
+
+    /// Total objective penalty from soft rules and PreferNot entries
+    pub fn soft_penalty(&self, solution: &[Room], all_people: &[Person]) -> i32 {
+        let mut penalty = 0;
+
+        if self.rules.iter().any(|rule| rule.strength != Strength::Hard) {
+            penalty += self.violations(solution, all_people).iter()
+                .map(|v| v.penalty)
+                .sum::<i32>();
+        }
+
+        if self.prefer_not_penalty != 0 {
+            penalty += self.soft_avoid_violations(solution, all_people).iter()
+                .map(|v| v.penalty)
+                .sum::<i32>();
+        }
+
+        penalty
+    }
+}
+
+impl AttributeRule {
+    /// Whether `person` counts toward the rule; staff in a "Boys + Staff" room don't count
+    /// toward a rule written for Boys
+    fn covers(&self, person: &Person) -> bool {
+        self.category.as_ref().is_none_or(|c| c == &person.category)
+    }
+}
+
+impl RuleKind {
+    /// How badly a finished room breaks this rule, 0 if it doesn't
+    fn excess(&self, members: &[&Person]) -> usize {
+        match self {
+            RuleKind::MaxSame { attribute, max } => {
+                let mut counts: HashMap<&str, usize> = HashMap::new();
+                for member in members {
+                    if let Some(value) = attribute_value(member, attribute) {
+                        *counts.entry(value).or_insert(0) += 1;
+                    }
+                }
+                counts.values().map(|count| count.saturating_sub(*max)).sum()
+            }
+            RuleKind::MaxSpread { attribute, max } => {
+                let values: Vec<f64> = members.iter()
+                    .filter_map(|m| numeric_value(m, attribute))
+                    .collect();
+                let highest = values.iter().cloned().fold(f64::MIN, f64::max);
+                let lowest = values.iter().cloned().fold(f64::MAX, f64::min);
+                if values.len() > 1 && highest - lowest > *max {
+                    (highest - lowest - max).ceil() as usize
+                } else {
+                    0
+                }
+            }
+            RuleKind::MinCount { attribute, value, min } => {
+                let have = members.iter()
+                    .filter(|m| matches_value(m, attribute, value))
+                    .count();
+                min.saturating_sub(have)
+            }
+        }
+    }
+}
+
+/// Puts each person from a `*` category in the pool holding most of their choices, or
+/// failing that the one with fewest people, and returns the pool index for each
+fn spread_wildcards<'p>(
+    pool_members: &mut [BTreeSet<&'p str>],
+    wildcards: &BTreeSet<&str>,
+    people: &'p [Person],
+) -> Vec<(&'p str, usize)> {
+    let mut pool_of_person: HashMap<&str, usize> = people.iter()
+        .filter_map(|p| pool_members.iter()
+            .position(|members| members.contains(p.category.as_str()))
+            .map(|pool| (p.id.as_str(), pool)))
+        .collect();
+    let mut sizes: Vec<usize> = (0..pool_members.len())
+        .map(|pool| pool_of_person.values().filter(|p| **p == pool).count())
+        .collect();
+    let mut spread = Vec::new();
+
+    for person in people.iter().filter(|p| wildcards.contains(p.category.as_str())) {
+        let chosen_in = |pool: usize| person.choices.iter()
+            .filter(|c| pool_of_person.get(c.as_str()) == Some(&pool))
+            .count();
+        let pool = (0..pool_members.len())
+            .max_by_key(|pool| (chosen_in(*pool), Reverse(sizes[*pool]), Reverse(*pool)))
+            .expect("spread_wildcards needs at least one pool");
+
+        pool_of_person.insert(person.id.as_str(), pool);
+        sizes[pool] += 1;
+        pool_members[pool].insert(person.category.as_str());
+        spread.push((person.id.as_str(), pool));
+    }
+
+    spread
+}
+
+/// People with a Role column of "Leader" (chaperones, counsellors, ...)
+pub fn is_leader(person: &Person) -> bool {
+    attribute_value(person, "Role")
+        .map(|role| role.eq_ignore_ascii_case("leader"))
+        .unwrap_or(false)
+}
+
+/// Attribute lookup ignoring header case, blank values count as missing
+pub fn attribute_value<'a>(person: &'a Person, attribute: &str) -> Option<&'a str> {
+    person.attributes.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(attribute))
+        .map(|(_, value)| value.as_str())
+        .filter(|value| !value.is_empty())
+}
+
+fn numeric_value(person: &Person, attribute: &str) -> Option<f64> {
+    attribute_value(person, attribute).and_then(|value| value.parse::<f64>().ok())
+}
+
+fn matches_value(person: &Person, attribute: &str, value: &str) -> bool {
+    attribute_value(person, attribute)
+        .map(|v| v.eq_ignore_ascii_case(value))
+        .unwrap_or(false)
+}
+
+fn parse_rule(line: &str, people: &[Person]) -> Result<AttributeRule, String> {
+    let (scope, body) = line.split_once(':')
+        .ok_or("expected '<category or *>: <rule>'")?;
+
+    let scope = scope.trim();
+    let category = if scope == "*" {
+        None
+    } else if people.iter().any(|p| p.category == scope) {
+        Some(scope.to_string())
+    } else {
+        return Err(format!("no one has category '{}'", scope));
+    };
+
+    // Optional trailing "soft" or "soft <weight>"
+    let mut words: Vec<&str> = body.split_whitespace().collect();
+    let mut strength = Strength::Hard;
+    if words.len() >= 2 && words[words.len() - 2].eq_ignore_ascii_case("soft") {
+        let weight = words[words.len() - 1];
+        let weight = weight.parse::<i32>()
+            .ok()
+            .filter(|w| *w >= 0)
+            .ok_or_else(|| format!("'{}' is not a valid soft weight", weight))?;
+        strength = Strength::Soft(weight);
+        words.truncate(words.len() - 2);
+    } else if words.last().map(|w| w.eq_ignore_ascii_case("soft")).unwrap_or(false) {
+        strength = Strength::Soft(DEFAULT_SOFT_WEIGHT);
+        words.truncate(words.len() - 1);
+    }
+    let body = words.join(" ");
+
+    let (keyword, rest) = body.split_once(char::is_whitespace)
+        .ok_or("expected 'same', 'spread' or 'count'")?;
+
+    let kind = match keyword.to_lowercase().as_str() {
+        "same" => {
+            let (attribute, max) = rest.split_once("<=").ok_or("expected 'same <Column> <= N'")?;
+            let max = max.trim().parse::<usize>()
+                .map_err(|_| format!("'{}' is not a whole number", max.trim()))?;
+            RuleKind::MaxSame { attribute: attribute.trim().to_string(), max }
+        }
+        "spread" => {
+            let (attribute, max) = rest.split_once("<=").ok_or("expected 'spread <Column> <= N'")?;
+            let max = max.trim().parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number", max.trim()))?;
+            RuleKind::MaxSpread { attribute: attribute.trim().to_string(), max }
+        }
+        "count" => {
+            let (condition, min) = rest.split_once(">=").ok_or("expected 'count <Column> = <Value> >= N'")?;
+            let (attribute, value) = condition.split_once('=').ok_or("expected 'count <Column> = <Value> >= N'")?;
+            let min = min.trim().parse::<usize>()
+                .map_err(|_| format!("'{}' is not a whole number", min.trim()))?;
+            RuleKind::MinCount {
+                attribute: attribute.trim().to_string(),
+                value: value.trim().to_string(),
+                min,
+            }
+        }
+        other => return Err(format!("unknown rule '{}', expected 'same', 'spread' or 'count'", other)),
+    };
+
+    let attribute = match &kind {
+        RuleKind::MaxSame { attribute, .. }
+        | RuleKind::MaxSpread { attribute, .. }
+        | RuleKind::MinCount { attribute, .. } => attribute,
+    };
+    if !people.iter().any(|p| p.attributes.keys().any(|key| key.eq_ignore_ascii_case(attribute))) {
+        return Err(format!("no column named '{}'", attribute));
+    }
+
+    Ok(AttributeRule {
+        category,
+        kind,
+        strength,
+        text: line.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, staff_example};
+
+    #[test]
+    fn staff_are_spread_without_mixing_boys_and_girls() {
+        let people = staff_example();
+        let constraints = constraints(&people, "Staff, *", 4);
+
+        for a in &people {
+            for b in &people {
+                if constraints.pool_for(a) == constraints.pool_for(b) {
+                    assert!(constraints.compatible(&a.category, &b.category), "{} and {} share a pool", a.id, b.id);
+                }
+            }
+        }
+        // s1 chose a boy; s2 chose no one, so goes to the smaller pool
+        assert_eq!(constraints.pool_for(&people[10]), "Boys + Staff");
+        assert_eq!(constraints.pool_for(&people[11]), "Girls + Staff");
+        assert_eq!(constraints.pool_for(&people[0]), "Boys + Staff");
+        assert_eq!(constraints.pool_for(&people[5]), "Girls + Staff");
+    }
+
+    #[test]
+    fn only_wildcard_categories_share_one_pool() {
+        let people = vec![person("a", "Staff", &[]), person("b", "Nurses", &[])];
+        let constraints = constraints(&people, "Staff, *\nNurses, *", 4);
+
+        assert_eq!(constraints.pool_for(&people[0]), "Nurses + Staff");
+        assert_eq!(constraints.pool_for(&people[1]), "Nurses + Staff");
+    }
+
+    #[test]
+    fn chain_that_is_not_transitive_is_rejected() {
+        let people = vec![
+            person("a", "Grade 9", &[]),
+            person("b", "Grade 10", &[]),
+            person("c", "Grade 11", &[]),
+        ];
+        let options = SolveOptions {
+            category_compatibility: "Grade 9, Grade 10\nGrade 10, Grade 11".to_string(),
+            ..Default::default()
+        };
+        let error = Constraints::from_options(&options, &people).unwrap_err();
+        assert!(error.contains("Grade 11") && error.contains("Grade 9"), "{}", error);
+
+        let constraints = constraints(&people, "Grade 9, Grade 10, Grade 11", 4);
+        assert!(people.iter().all(|p| constraints.pool_for(p) == "Grade 10 + Grade 11 + Grade 9"));
+    }
+
+    #[test]
+    fn rule_for_one_category_covers_only_its_pools() {
+        let mut people = staff_example();
+        for person in &mut people {
+            person.attributes.insert("School".to_string(), "Lincoln".to_string());
+        }
+        let options = SolveOptions {
+            category_compatibility: "Staff, *".to_string(),
+            room_rules: "Boys: same School <= 1\nStaff: same School <= 2".to_string(),
+            ..Default::default()
+        };
+        let constraints = Constraints::from_options(&options, &people).unwrap();
+
+        let girls_rules: Vec<&str> = constraints.rules_for("Girls + Staff").map(|r| r.text.as_str()).collect();
+        assert_eq!(girls_rules, vec!["Staff: same School <= 2"]);
+        assert_eq!(constraints.rules_for("Boys + Staff").count(), 2);
+    }
+
+    /// Boys b1.. from `schools`, aged `ages`, and a staff member from Lincoln
+    fn campers(schools: &[&str], ages: &[&str]) -> Vec<Person> {
+        let mut people: Vec<Person> = schools.iter().zip(ages).enumerate()
+            .map(|(i, (school, age))| {
+                let mut boy = person(&format!("b{}", i + 1), "Boys", &[]);
+                boy.attributes.insert("School".to_string(), school.to_string());
+                boy.attributes.insert("Age".to_string(), age.to_string());
+                boy.attributes.insert("Returning".to_string(), "No".to_string());
+                boy
+            })
+            .collect();
+        let mut staff = person("s1", "Staff", &[]);
+        staff.attributes.insert("School".to_string(), "Lincoln".to_string());
+        people.push(staff);
+        people
+    }
+
+    fn room(category: &str, members: &[&str], max_size: usize) -> Room {
+        Room {
+            category: category.to_string(),
+            members: members.iter().map(|id| id.to_string()).collect(),
+            max_size,
+        }
+    }
+
+    #[test]
+    fn rules_parse_with_optional_soft_weights() {
+        let people = campers(&["Lincoln"], &["12"]);
+        let text = "Boys: same School <= 2\n*: spread Age <= 1.5 soft 50\nBoys: count Returning = Yes >= 1 soft";
+        let rules = Constraints::parse(text, &people).unwrap().rules;
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].category.as_deref(), Some("Boys"));
+        assert!(matches!(&rules[0].kind, RuleKind::MaxSame { attribute, max: 2 } if attribute == "School"));
+        assert_eq!(rules[0].strength, Strength::Hard);
+        assert_eq!(rules[1].category, None);
+        assert!(matches!(&rules[1].kind, RuleKind::MaxSpread { attribute, max } if attribute == "Age" && *max == 1.5));
+        assert_eq!(rules[1].strength, Strength::Soft(50));
+        assert!(matches!(&rules[2].kind,
+            RuleKind::MinCount { attribute, value, min: 1 } if attribute == "Returning" && value == "Yes"));
+        assert_eq!(rules[2].strength, Strength::Soft(DEFAULT_SOFT_WEIGHT));
+    }
+
+    #[test]
+    fn malformed_rules_name_their_line() {
+        let people = campers(&["Lincoln"], &["12"]);
+        for (line, expected) in [
+            ("same School <= 2", "expected '<category or *>: <rule>'"),
+            ("Girls: same School <= 2", "no one has category 'Girls'"),
+            ("Boys: same Height <= 2", "no column named 'Height'"),
+            ("Boys: same School 2", "expected 'same <Column> <= N'"),
+            ("Boys: same School <= two", "'two' is not a whole number"),
+            ("Boys: count Returning >= 1", "expected 'count <Column> = <Value> >= N'"),
+            ("Boys: most School <= 2", "unknown rule 'most'"),
+            ("Boys: same School <= 2 soft -5", "'-5' is not a valid soft weight"),
+        ] {
+            let error = Constraints::parse(&format!("*: same School <= 3\n{}", line), &people).unwrap_err();
+            assert!(error.starts_with("Room rules line 2: ") && error.contains(expected), "{}", error);
+        }
+    }
+
+    #[test]
+    fn allows_refuses_a_third_from_one_school_or_too_wide_an_age_gap() {
+        let people = campers(&["Lincoln", "Lincoln", "Lincoln", "Adams"], &["12", "13", "12", "15"]);
+        let constraints = Constraints::parse("Boys: same School <= 2\nBoys: spread Age <= 2", &people).unwrap();
+        let target = room("Boys", &["b1", "b2"], 4);
+
+        assert!(!constraints.allows(&people[2], &target, &people), "third from Lincoln");
+        assert!(!constraints.allows(&people[3], &target, &people), "15 next to 12");
+        assert!(constraints.allows(&people[3], &room("Boys", &["b2"], 4), &people));
+    }
+
+    #[test]
+    fn min_count_keeps_the_last_slot_for_a_match() {
+        let mut people = campers(&["Lincoln", "Lincoln", "Adams", "Adams"], &["12", "12", "12", "12"]);
+        people[3].attributes.insert("Returning".to_string(), "Yes".to_string());
+        let constraints = Constraints::parse("Boys: count Returning = Yes >= 1", &people).unwrap();
+
+        assert!(constraints.allows(&people[1], &room("Boys", &[], 3), &people));
+        assert!(!constraints.allows(&people[2], &room("Boys", &["b1", "b2"], 3), &people));
+        assert!(constraints.allows(&people[3], &room("Boys", &["b1", "b2"], 3), &people));
+    }
+
+    #[test]
+    fn soft_penalty_is_each_weight_times_how_far_over() {
+        let mut people = campers(&["Lincoln", "Lincoln", "Lincoln", "Lincoln"], &["10", "12", "14", "12"]);
+        people[0].prefer_not.push("b2".to_string());
+        let mut constraints = Constraints::parse(
+            "Boys: same School <= 2 soft 50\nBoys: spread Age <= 2 soft 7",
+            &people,
+        ).unwrap();
+        constraints.prefer_not_penalty = 3;
+        let rooms = [room("Boys", &["b1", "b2", "b3", "b4"], 4)];
+
+        // Two Lincolns too many, ages 2 further apart than allowed and one PreferNot
+        assert_eq!(constraints.soft_penalty(&rooms, &people), 50 * 2 + 7 * 2 + 3);
+        assert!(constraints.hard_violations(&rooms, &people).is_empty());
+    }
+
+    #[test]
+    fn staff_do_not_count_toward_a_rule_for_boys() {
+        let people = campers(&["Lincoln", "Lincoln"], &["12", "12"]);
+        let options = SolveOptions {
+            category_compatibility: "Staff, *".to_string(),
+            room_rules: "Boys: same School <= 2".to_string(),
+            ..Default::default()
+        };
+        let constraints = Constraints::from_options(&options, &people).unwrap();
+        let pool = constraints.pool_for(&people[0]).to_string();
+        let rooms = [room(&pool, &["b1", "b2", "s1"], 3)];
+
+        assert!(constraints.allows(&people[2], &room(&pool, &["b1", "b2"], 3), &people));
+        assert!(constraints.violations(&rooms, &people).is_empty());
+    }
+}
+
@@ -0,0 +1,534 @@
+use std::collections::{HashMap, HashSet};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Serialize, Deserialize};
+use crate::constraints::{self, Constraints, LeaderPolicy};
+use crate::solver::{can_add_person_to_room, Person, Room, RoomDistribution, Solution};
+
+/// How phase 1 brings together people who chose each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pairing {
+    /// One mutual pair at a time, trying pairs from the best solution so far first
+    #[default]
+    Pairs,
+    /// Each person with as many of their mutual choices as fit in one room
+    Groups,
+}
+
+/// Greedy construction for one pool of rooms. Set up once per pool, then each call to
+/// `build` makes a complete solution from its own seed:
+///
+/// - phase 0 spreads leaders across the rooms, when the leader policy asks for it;
+/// - phase 1 places people who chose each other together, by `Pairing`;
+/// - phase 2 puts everyone else in the valid room holding most of their choices.
+pub struct SolutionBuilder<'a> {
+    people: &'a [Person],
+    constraints: &'a Constraints,
+    pool: String,
+    distribution: RoomDistribution,
+    mutual_counts: HashMap<String, usize>,
+    pair_hints: HashMap<(String, String), i32>,
+    pairing: Pairing,
+}
+
+impl<'a> SolutionBuilder<'a> {
+    pub fn new(
+        pool: &str,
+        people: &'a [Person],
+        distribution: &RoomDistribution,
+        constraints: &'a Constraints,
+    ) -> SolutionBuilder<'a> {
+        SolutionBuilder {
+            people,
+            constraints,
+            pool: pool.to_string(),
+            distribution: distribution.clone(),
+            mutual_counts: mutual_counts(people, constraints),
+            pair_hints: HashMap::new(),
+            pairing: Pairing::default(),
+        }
+    }
+
+    pub fn pairing(mut self, pairing: Pairing) -> SolutionBuilder<'a> {
+        self.pairing = pairing;
+        self
+    }
+
+    /// Pairs tried first by pair-based phase 1, highest score first, e.g. the pairs that
+    /// share a room in the best solution so far
+    pub fn set_pair_hints(&mut self, pair_hints: HashMap<(String, String), i32>) {
+        self.pair_hints = pair_hints;
+    }
+
+    /// Builds one solution. Fails with the ID of someone who fits in no room, even after
+    /// trying to make space for them.
+    pub fn build(&self, seed: usize) -> Result<Solution, String> {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let mut solution: Solution = self.distribution.sizes.iter()
+            .map(|&size| Room::new(self.pool.clone(), size))
+            .collect();
+        let mut placed: HashSet<String> = HashSet::new();
+
+        if self.constraints.leader_policy != LeaderPolicy::None {
+            self.spread_leaders(&mut solution, &mut placed, &mut rng);
+        }
+
+        // Most connected people first on even seeds, a random order on odd ones
+        let mut people_list: Vec<&Person> = self.people.iter().collect();
+        if seed.is_multiple_of(2) {
+            people_list.sort_by_key(|p| -(self.mutual_counts.get(&p.id).copied().unwrap_or(0) as i32));
+        } else {
+            people_list.shuffle(&mut rng);
+        }
+
+        match self.pairing {
+            Pairing::Pairs => self.place_pairs(&people_list, &mut solution, &mut placed, &mut rng),
+            Pairing::Groups => self.place_groups(&people_list, &mut solution, &mut placed, &mut rng),
+        }
+
+        place_remaining(&mut solution, self.people, self.constraints, &mut placed, &mut rng)?;
+
+        Ok(solution)
+    }
+
+    /// Phase 0: leaders go into the emptiest valid room, so one per room before doubling up
+    fn spread_leaders(&self, solution: &mut Solution, placed: &mut HashSet<String>, rng: &mut StdRng) {
+        let (people, constraints) = (self.people, self.constraints);
+        let mut leaders: Vec<&Person> = people.iter()
+            .filter(|p| constraints::is_leader(p))
+            .collect();
+        leaders.shuffle(rng);
+
+        for leader in leaders {
+            let mut candidate_rooms: Vec<usize> = solution.iter()
+                .enumerate()
+                .filter(|(_, r)| r.category == constraints.pool_for(leader) && r.has_space())
+                .filter(|(_, r)| can_add_person_to_room(leader, r, people, constraints))
+                .map(|(i, _)| i)
+                .collect();
+            candidate_rooms.shuffle(rng);
+            candidate_rooms.sort_by_key(|&i| {
+                solution[i].members.iter()
+                    .filter(|id| people.iter().any(|p| &p.id == *id && constraints::is_leader(p)))
+                    .count()
+            });
+
+            if let Some(&room_idx) = candidate_rooms.first() {
+                solution[room_idx].add_person(leader.id.clone());
+                placed.insert(leader.id.clone());
+            }
+        }
+    }
+
+    /// Pair-based phase 1: each person joins one mutual choice in a room with space for both
+    fn place_pairs(&self, people_list: &[&Person], solution: &mut Solution, placed: &mut HashSet<String>, rng: &mut StdRng) {
+        let (people, constraints) = (self.people, self.constraints);
+
+        for person in people_list {
+            if placed.contains(&person.id) {
+                continue;
+            }
+
+            // Sort choices by hint score (if we have hints)
+            let mut choices_with_scores: Vec<(&String, i32)> = person.choices.iter()
+                .map(|choice_name| {
+                    let pair = if &person.id < choice_name {
+                        (person.id.clone(), choice_name.clone())
+                    } else {
+                        (choice_name.clone(), person.id.clone())
+                    };
+                    let score = self.pair_hints.get(&pair).copied().unwrap_or(0);
+                    (choice_name, score)
+                })
+                .collect();
+
+            choices_with_scores.sort_by_key(|(_, score)| -score);
+
+            // Try to place with mutual friends, prioritizing hinted pairs
+            for (choice_name, _) in choices_with_scores {
+                if placed.contains(choice_name) {
+                    // Leaders were placed in phase 0; join a mutually chosen leader's room if it fits
+                    let leader_room = solution.iter().position(|r| r.members.contains(choice_name));
+                    let is_mutual_leader = people.iter().any(|p| {
+                        &p.id == choice_name && constraints::is_leader(p) && p.choices.contains(&person.id)
+                    });
+                    if let (true, Some(room_idx)) = (is_mutual_leader, leader_room) {
+                        if solution[room_idx].has_space()
+                            && can_add_person_to_room(person, &solution[room_idx], people, constraints) {
+                            solution[room_idx].add_person(person.id.clone());
+                            placed.insert(person.id.clone());
+                            break;
+                        }
+                    }
+                    continue;
+                }
+
+                let Some(friend) = people.iter().find(|p| &p.id == choice_name) else {
+                    continue;
+                };
+                if !constraints.compatible(&friend.category, &person.category) || !friend.choices.contains(&person.id) {
+                    continue;
+                }
+
+                let mut candidate_rooms: Vec<usize> = solution.iter()
+                    .enumerate()
+                    .filter(|(_, r)| r.category == constraints.pool_for(person) &&
+                                     r.members.len() + 2 <= r.max_size)
+                    .map(|(i, _)| i)
+                    .collect();
+                candidate_rooms.shuffle(rng);
+
+                for room_idx in candidate_rooms {
+                    if !can_add_person_to_room(person, &solution[room_idx], people, constraints) {
+                        continue;
+                    }
+
+                    // The friend has to fit alongside the person, not just the current members
+                    solution[room_idx].add_person(person.id.clone());
+                    if can_add_person_to_room(friend, &solution[room_idx], people, constraints) {
+                        solution[room_idx].add_person(friend.id.clone());
+                        placed.insert(person.id.clone());
+                        placed.insert(friend.id.clone());
+                        break;
+                    }
+                    solution[room_idx].members.pop();
+                }
+
+                if placed.contains(&person.id) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Group-based phase 1: each person goes into a room with as many of their unplaced
+    /// mutual choices as fit there, most shared choices first
+    fn place_groups(&self, people_list: &[&Person], solution: &mut Solution, placed: &mut HashSet<String>, rng: &mut StdRng) {
+        let (people, constraints) = (self.people, self.constraints);
+
+        for person in people_list {
+            if placed.contains(&person.id) {
+                continue;
+            }
+
+            let mut mutual_friends: Vec<&Person> = person.choices.iter()
+                .filter_map(|choice_name| people.iter().find(|p| &p.id == choice_name))
+                .filter(|friend| {
+                    constraints.compatible(&friend.category, &person.category)
+                        && !placed.contains(&friend.id)
+                        && friend.choices.contains(&person.id)
+                })
+                .collect();
+            if mutual_friends.is_empty() {
+                continue;
+            }
+            mutual_friends.sort_by_key(|f| {
+                -(person.choices.iter().filter(|c| f.choices.contains(c)).count() as i32)
+            });
+
+            let mut candidate_rooms: Vec<usize> = solution.iter()
+                .enumerate()
+                .filter(|(_, r)| r.category == constraints.pool_for(person) &&
+                                 r.members.len() + 2 <= r.max_size)
+                .map(|(i, _)| i)
+                .collect();
+            candidate_rooms.shuffle(rng);
+
+            for room_idx in candidate_rooms {
+                if !can_add_person_to_room(person, &solution[room_idx], people, constraints) {
+                    continue;
+                }
+                solution[room_idx].add_person(person.id.clone());
+
+                // Each friend has to fit alongside everyone added before them
+                let mut group = vec![person.id.clone()];
+                for friend in &mutual_friends {
+                    if solution[room_idx].has_space()
+                        && can_add_person_to_room(friend, &solution[room_idx], people, constraints) {
+                        solution[room_idx].add_person(friend.id.clone());
+                        group.push(friend.id.clone());
+                    }
+                }
+
+                if group.len() > 1 {
+                    placed.extend(group);
+                    break;
+                }
+                solution[room_idx].members.pop();
+            }
+        }
+    }
+}
+
+/// How many of each person's choices chose them back, used to place the most
+/// connected people first
+fn mutual_counts(people: &[Person], constraints: &Constraints) -> HashMap<String, usize> {
+    people.iter()
+        .map(|person| {
+            let count = person.choices.iter()
+                .filter(|choice_name| {
+                    people.iter().any(|other|
+                        &other.id == *choice_name &&
+                        constraints.compatible(&other.category, &person.category) &&
+                        other.choices.contains(&person.id)
+                    )
+                })
+                .count();
+            (person.id.clone(), count)
+        })
+        .collect()
+}
+
+/// Greedy phase 2: puts everyone not yet in `placed` into the valid room holding most
+/// of their choices. Someone who fits nowhere gets a repair attempt; if that fails too,
+/// the error is their ID.
+pub fn place_remaining(
+    solution: &mut Solution,
+    people: &[Person],
+    constraints: &Constraints,
+    placed: &mut HashSet<String>,
+    rng: &mut StdRng,
+) -> Result<(), String> {
+    let mut remaining: Vec<&Person> = people.iter()
+        .filter(|p| !placed.contains(&p.id))
+        .collect();
+    remaining.shuffle(rng);
+
+    for person in remaining {
+        let mut best_rooms: Vec<(usize, usize)> = Vec::new();
+    
+        for (idx, room) in solution.iter().enumerate() {
+            if room.category != constraints.pool_for(person) || !room.has_space() {
+                continue;
+            }
+        
+            if !can_add_person_to_room(person, room, people, constraints) {
+                continue;
+            }
+        
+            let choice_count = person.choices.iter()
+                .filter(|choice| room.members.contains(choice))
+                .count();
+        
+            best_rooms.push((idx, choice_count));
+        }
+    
+        if best_rooms.is_empty() {
+            if !repair_placement(person, solution, people, constraints, rng) {
+                return Err(person.id.clone());
+            }
+            placed.insert(person.id.clone());
+            continue;
+        }
+    
+        best_rooms.sort_by_key(|(_, count)| -(*count as i32));
+    
+        let max_count = best_rooms[0].1;
+        let top_rooms: Vec<usize> = best_rooms.iter()
+            .filter(|(_, count)| *count == max_count)
+            .map(|(idx, _)| *idx)
+            .collect();
+    
+        let room_idx = top_rooms[rng.gen_range(0..top_rooms.len())];
+        solution[room_idx].add_person(person.id.clone());
+        placed.insert(person.id.clone());
+    }
+
+    Ok(())
+}
+
+/// Makes space for someone who fits in no room with space: finds a room they would fit
+/// in without one of its members, and moves that member to another room of the pool
+/// with space that they fit in. Leaders stay put when they are spread on purpose.
+fn repair_placement(
+    person: &Person,
+    solution: &mut Solution,
+    people: &[Person],
+    constraints: &Constraints,
+    rng: &mut StdRng,
+) -> bool {
+    let pool = constraints.pool_for(person);
+    let mut rooms: Vec<usize> = (0..solution.len())
+        .filter(|&i| solution[i].category == pool)
+        .collect();
+    rooms.shuffle(rng);
+
+    for &room_idx in &rooms {
+        for slot in 0..solution[room_idx].members.len() {
+            let Some(ejected) = people.iter().find(|p| p.id == solution[room_idx].members[slot]) else {
+                continue;
+            };
+            if constraints.leader_policy != LeaderPolicy::None && constraints::is_leader(ejected) {
+                continue;
+            }
+        
+            let ejected_id = solution[room_idx].members.remove(slot);
+            if can_add_person_to_room(person, &solution[room_idx], people, constraints) {
+                let new_room = rooms.iter().copied().find(|&other| {
+                    other != room_idx
+                        && solution[other].has_space()
+                        && can_add_person_to_room(ejected, &solution[other], people, constraints)
+                });
+                if let Some(other) = new_room {
+                    solution[other].add_person(ejected_id);
+                    solution[room_idx].add_person(person.id.clone());
+                    return true;
+                }
+            }
+            solution[room_idx].members.insert(slot, ejected_id);
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_everyone_placed_once, constraints, person};
+
+    /// `count` boys, the first `leaders` of them leaders who all chose each other
+    fn boys_with_leaders(count: usize, leaders: usize) -> Vec<Person> {
+        let leader_ids: Vec<String> = (1..=leaders).map(|i| format!("b{}", i)).collect();
+        (1..=count)
+            .map(|i| {
+                let id = format!("b{}", i);
+                let mut boy = person(&id, "Boys", &[]);
+                if i <= leaders {
+                    boy.attributes.insert("Role".to_string(), "Leader".to_string());
+                    boy.choices = leader_ids.iter().filter(|other| **other != id).cloned().collect();
+                }
+                boy
+            })
+            .collect()
+    }
+
+    fn leaders_per_room(solution: &Solution, people: &[Person]) -> Vec<usize> {
+        solution.iter()
+            .map(|room| room.members.iter()
+                .filter(|id| people.iter().any(|p| &p.id == *id && constraints::is_leader(p)))
+                .count())
+            .collect()
+    }
+
+    #[test]
+    fn one_per_room_gives_every_room_exactly_one_leader() {
+        let people = boys_with_leaders(9, 3);
+        let mut constraints = constraints(&people, "", 3);
+        constraints.leader_policy = LeaderPolicy::OnePerRoom;
+        let distribution = RoomDistribution { sizes: vec![3, 3, 3] };
+        let builder = SolutionBuilder::new("Boys", &people, &distribution, &constraints);
+
+        for seed in 0..20 {
+            let solution = builder.build(seed).unwrap();
+            assert_eq!(leaders_per_room(&solution, &people), vec![1, 1, 1], "seed {}", seed);
+            assert!(constraints.hard_violations(&solution, &people).is_empty());
+        }
+    }
+
+    #[test]
+    fn fill_first_spreads_leaders_before_doubling_up() {
+        let people = boys_with_leaders(12, 4);
+        let mut constraints = constraints(&people, "", 4);
+        constraints.leader_policy = LeaderPolicy::FillFirst;
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let builder = SolutionBuilder::new("Boys", &people, &distribution, &constraints);
+
+        for seed in 0..20 {
+            let mut leaders = leaders_per_room(&builder.build(seed).unwrap(), &people);
+            leaders.sort();
+            assert_eq!(leaders, vec![1, 1, 2], "seed {}", seed);
+        }
+    }
+
+    fn room(members: &[&str], max_size: usize) -> Room {
+        let mut room = Room::new("Boys".to_string(), max_size);
+        room.members = members.iter().map(|id| id.to_string()).collect();
+        room
+    }
+
+    #[test]
+    fn repair_makes_space_for_someone_greedy_placement_is_stuck_on() {
+        let mut people: Vec<Person> = ["a", "b", "c", "d"].iter().map(|id| person(id, "Boys", &[])).collect();
+        people[3].avoids.push("a".to_string());
+        let constraints = constraints(&people, "", 2);
+        // d avoids a, who is in the only room with space
+        let mut solution = vec![room(&["a"], 2), room(&["b", "c"], 2)];
+        let mut placed: HashSet<String> = ["a", "b", "c"].iter().map(|id| id.to_string()).collect();
+
+        place_remaining(&mut solution, &people, &constraints, &mut placed, &mut StdRng::seed_from_u64(1)).unwrap();
+
+        assert_everyone_placed_once(&solution, &people);
+        assert!(solution.iter().all(|r| !(r.members.contains(&"a".to_string()) && r.members.contains(&"d".to_string()))));
+        assert_eq!(placed.len(), 4);
+    }
+
+    #[test]
+    fn repair_gives_up_when_no_move_makes_space() {
+        let mut people: Vec<Person> = ["a", "b", "c", "d"].iter().map(|id| person(id, "Boys", &[])).collect();
+        // Nobody in the full room can move in with a to make space for d
+        for avoider in &mut people[1..] {
+            avoider.avoids.push("a".to_string());
+        }
+        let constraints = constraints(&people, "", 2);
+        let mut solution = vec![room(&["a"], 2), room(&["b", "c"], 2)];
+        let mut placed: HashSet<String> = ["a", "b", "c"].iter().map(|id| id.to_string()).collect();
+
+        let stuck = place_remaining(&mut solution, &people, &constraints, &mut placed, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(stuck, Err("d".to_string()));
+    }
+
+    /// Groups of `size` Boys in which everyone chose everyone else
+    fn friend_groups(groups: usize, size: usize) -> Vec<Person> {
+        (0..groups * size)
+            .map(|i| {
+                let group = i / size * size;
+                let friends: Vec<String> = (group..group + size).filter(|&j| j != i).map(|j| format!("b{}", j)).collect();
+                let friends: Vec<&str> = friends.iter().map(|f| f.as_str()).collect();
+                person(&format!("b{}", i), "Boys", &friends)
+            })
+            .collect()
+    }
+
+    fn room_of<'s>(solution: &'s Solution, id: &str) -> &'s Room {
+        solution.iter().find(|r| r.members.iter().any(|m| m == id)).unwrap()
+    }
+
+    #[test]
+    fn pairs_keeps_mutual_pairs_together() {
+        let people = friend_groups(6, 2);
+        let constraints = constraints(&people, "", 4);
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let builder = SolutionBuilder::new("Boys", &people, &distribution, &constraints).pairing(Pairing::Pairs);
+
+        for seed in 0..20 {
+            let solution = builder.build(seed).unwrap();
+            assert_everyone_placed_once(&solution, &people);
+            for person in &people {
+                assert!(room_of(&solution, &person.id).members.contains(&person.choices[0]),
+                        "seed {}: {} without {}", seed, person.id, person.choices[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn groups_keeps_whole_mutual_groups_together() {
+        let people = friend_groups(3, 3);
+        let constraints = constraints(&people, "", 3);
+        let distribution = RoomDistribution { sizes: vec![3, 3, 3] };
+        let builder = SolutionBuilder::new("Boys", &people, &distribution, &constraints).pairing(Pairing::Groups);
+
+        for seed in 0..20 {
+            let solution = builder.build(seed).unwrap();
+            assert_everyone_placed_once(&solution, &people);
+            for person in &people {
+                let room = room_of(&solution, &person.id);
+                assert!(person.choices.iter().all(|c| room.members.contains(c)), "seed {}: {:?}", seed, room.members);
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use crate::constraints::Constraints;
+use crate::solver::{self, SolveOptions, SolveResult};
+use crate::verify::Violation;
+
+/// The constraints a result was solved under, for rescoring it after an edit
+pub fn constraints_for(result: &SolveResult, max_room_size: usize, options: &SolveOptions) -> Result<Constraints, String> {
+    let mut constraints = Constraints::from_options(options, &result.people)?;
+    constraints.max_room_size = max_room_size;
+    Ok(constraints)
+}
+
+/// Pool and room index holding someone
+fn locate(result: &SolveResult, person_id: &str) -> Result<(String, usize), String> {
+    result.rooms_by_category.iter()
+        .find_map(|(category, rooms)| rooms.iter()
+            .position(|room| room.iter().any(|id| id == person_id))
+            .map(|room_idx| (category.clone(), room_idx)))
+        .ok_or_else(|| format!("{} is not in any room", person_id))
+}
+
+/// Moves someone into another room, or a new one when `room` is one past the last, and
+/// rescores. A room left empty is removed. Nothing stops the move breaking a rule; the
+/// rescored result lists whatever it breaks.
+pub fn move_person(
+    mut result: SolveResult,
+    person_id: &str,
+    category: &str,
+    room: usize,
+    constraints: &Constraints,
+) -> Result<SolveResult, String> {
+    let (from_category, from_room) = locate(&result, person_id)?;
+    let room_count = result.rooms_by_category.get(category)
+        .ok_or_else(|| format!("There are no {} rooms", category))?
+        .len();
+    if room > room_count {
+        return Err(format!("{} has no room {}", category, room + 1));
+    }
+    if from_category == category && from_room == room {
+        return Ok(result);
+    }
+
+    let rooms = &mut result.rooms_by_category;
+    rooms.get_mut(&from_category).unwrap()[from_room].retain(|id| id != person_id);
+    let target = rooms.get_mut(category).unwrap();
+    if room == target.len() {
+        target.push(Vec::new());
+    }
+    target[room].push(person_id.to_string());
+    let target_len = target[room].len();
+    let source_emptied = rooms[&from_category][from_room].is_empty();
+    if source_emptied {
+        rooms.get_mut(&from_category).unwrap().remove(from_room);
+    }
+
+    // Planned sizes follow the rooms: a new room is planned for whoever is in it, and an
+    // overfilled room grows
+    if let Some(sizes) = result.room_sizes.get_mut(category) {
+        if room == sizes.len() {
+            sizes.push(target_len);
+        } else if let Some(size) = sizes.get_mut(room) {
+            *size = (*size).max(target_len);
+        }
+    }
+    if source_emptied {
+        if let Some(sizes) = result.room_sizes.get_mut(&from_category) {
+            if from_room < sizes.len() {
+                sizes.remove(from_room);
+            }
+        }
+    }
+
+    let mut edited = vec![from_category];
+    if edited[0] != category {
+        edited.push(category.to_string());
+    }
+    result.rescore(constraints, &edited);
+    Ok(result)
+}
+
+/// Swaps two people's rooms and rescores
+pub fn swap_people(
+    mut result: SolveResult,
+    first_id: &str,
+    second_id: &str,
+    constraints: &Constraints,
+) -> Result<SolveResult, String> {
+    let (first_category, first_room) = locate(&result, first_id)?;
+    let (second_category, second_room) = locate(&result, second_id)?;
+    if first_category == second_category && first_room == second_room {
+        return Ok(result);
+    }
+
+    let rooms = &mut result.rooms_by_category;
+    for id in rooms.get_mut(&first_category).unwrap()[first_room].iter_mut() {
+        if id == first_id {
+            *id = second_id.to_string();
+        }
+    }
+    for id in rooms.get_mut(&second_category).unwrap()[second_room].iter_mut() {
+        if id == second_id {
+            *id = first_id.to_string();
+        }
+    }
+
+    let mut edited = vec![first_category];
+    if edited[0] != second_category {
+        edited.push(second_category);
+    }
+    result.rescore(constraints, &edited);
+    Ok(result)
+}
+
+/// One way to give an unhappy person a choice: move them into `room`, or swap them with
+/// `swap_with` there
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub person: String,
+    pub category: String,
+    pub room: usize,
+    pub swap_with: Option<String>,
+    /// Change in the overall objective; higher is better
+    pub objective_change: i32,
+    pub choice_score_change: i32,
+    /// Others who had a choice and would lose it
+    pub newly_unhappy: Vec<String>,
+}
+
+fn overall_objective(result: &SolveResult) -> i32 {
+    solver::objective(result.choice_score, result.without_choices, result.imbalance, result.soft_penalty)
+}
+
+/// A violation with its room named by who is in it, leaving out `moved`, rather than by
+/// index. Matches across an edit that moves those people or removes an emptied room.
+fn violation_key(
+    violation: &Violation,
+    rooms: &HashMap<String, Vec<Vec<String>>>,
+    moved: &[&str],
+) -> (Violation, Vec<String>) {
+    let mut key = violation.clone();
+    let mut members = Vec::new();
+    match &mut key {
+        Violation::UnknownPerson { category, room, .. }
+        | Violation::OverCapacity { category, room, .. }
+        | Violation::WrongCategory { category, room, .. }
+        | Violation::HardAvoid { category, room, .. }
+        | Violation::HardRule { category, room, .. } => {
+            if let Some(held) = rooms.get(category.as_str()).and_then(|r| r.get(*room)) {
+                members = held.iter()
+                    .filter(|id| !moved.contains(&id.as_str()))
+                    .cloned()
+                    .collect();
+                members.sort();
+            }
+            *room = 0;
+        }
+        Violation::Unplaced { .. } | Violation::PlacedMoreThanOnce { .. } => {}
+    }
+    (key, members)
+}
+
+/// Every move or swap that puts someone in a room with one of their choices without
+/// breaking a rule the rooms didn't already break. Least damaging first: the biggest
+/// objective change, then the fewest others left without a choice.
+pub fn suggest_fixes(result: &SolveResult, person_id: &str, constraints: &Constraints) -> Result<Vec<Suggestion>, String> {
+    let person = result.people.iter()
+        .find(|p| p.id == person_id)
+        .ok_or_else(|| format!("{} isn't in the spreadsheet", person_id))?;
+    let (own_category, own_room) = locate(result, person_id)?;
+    let before = overall_objective(result);
+    let unhappy_before: HashSet<&str> = result.person_stats.iter()
+        .filter(|(_, stats)| stats.is_unhappy())
+        .map(|(id, _)| id.as_str())
+        .collect();
+
+    // Rooms holding at least one of their choices
+    let mut targets: Vec<(String, usize)> = result.rooms_by_category.iter()
+        .flat_map(|(category, rooms)| rooms.iter().enumerate()
+            .filter(|(_, room)| person.choices.iter().any(|c| room.contains(c)))
+            .map(move |(room_idx, _)| (category.clone(), room_idx)))
+        .filter(|target| *target != (own_category.clone(), own_room))
+        .collect();
+    targets.sort();
+
+    let mut suggestions = Vec::new();
+    for (category, room_idx) in targets {
+        let members = &result.rooms_by_category[&category][room_idx];
+        let edits = std::iter::once(None).chain(members.iter().map(Some));
+
+        for swap_with in edits {
+            let edited = match swap_with {
+                None => move_person(result.clone(), person_id, &category, room_idx, constraints)?,
+                Some(other) => swap_people(result.clone(), person_id, other, constraints)?,
+            };
+
+            let satisfied = edited.person_stats.get(person_id).is_some_and(|s| s.choices_received > 0);
+            let moved: Vec<&str> = std::iter::once(person_id).chain(swap_with.map(|id| id.as_str())).collect();
+            let already_broken: Vec<_> = result.violations.iter()
+                .map(|v| violation_key(v, &result.rooms_by_category, &moved))
+                .collect();
+            let breaks_more = edited.violations.iter()
+                .any(|v| !already_broken.contains(&violation_key(v, &edited.rooms_by_category, &moved)));
+            if !satisfied || breaks_more {
+                continue;
+            }
+
+            let mut newly_unhappy: Vec<String> = edited.person_stats.iter()
+                .filter(|(id, stats)| stats.is_unhappy() && !unhappy_before.contains(id.as_str()))
+                .map(|(id, _)| id.clone())
+                .collect();
+            newly_unhappy.sort();
+
+            suggestions.push(Suggestion {
+                person: person_id.to_string(),
+                category: category.clone(),
+                room: room_idx,
+                swap_with: swap_with.cloned(),
+                objective_change: overall_objective(&edited) - before,
+                choice_score_change: edited.choice_score - result.choice_score,
+                newly_unhappy,
+            });
+        }
+    }
+
+    suggestions.sort_by(|a, b| b.objective_change.cmp(&a.objective_change)
+        .then(a.newly_unhappy.len().cmp(&b.newly_unhappy.len())));
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, result};
+
+    #[test]
+    fn move_that_empties_a_room_keeps_a_later_room_s_violation_as_it_was() {
+        // Rooms of at most 3; the third room is already over
+        let mut people = vec![person("b1", "Boys", &["b2"])];
+        people.extend(["b2", "b3", "b4", "b5", "b6", "b7"].map(|id| person(id, "Boys", &[])));
+        let constraints = constraints(&people, "", 3);
+        let result = result(people, &[
+            ("Boys", vec![vec!["b1"], vec!["b2", "b3"], vec!["b4", "b5", "b6", "b7"]], vec![3, 3, 3]),
+        ], &constraints);
+        assert_eq!(result.violations.len(), 1);
+
+        let suggestions = suggest_fixes(&result, "b1", &constraints).unwrap();
+
+        let moved = suggestions.iter().find(|s| s.swap_with.is_none()).unwrap();
+        assert_eq!((moved.category.as_str(), moved.room), ("Boys", 1));
+    }
+
+    #[test]
+    fn move_into_an_avoided_roommate_is_not_suggested() {
+        let mut people = vec![person("b1", "Boys", &["b2"]), person("b2", "Boys", &[])];
+        let mut b3 = person("b3", "Boys", &[]);
+        b3.avoids.push("b1".to_string());
+        people.extend([b3, person("b4", "Boys", &[])]);
+        let constraints = constraints(&people, "", 3);
+        let result = result(people, &[
+            ("Boys", vec![vec!["b1", "b4"], vec!["b2", "b3"]], vec![2, 2]),
+        ], &constraints);
+
+        let suggestions = suggest_fixes(&result, "b1", &constraints).unwrap();
+
+        let swaps: Vec<Option<&str>> = suggestions.iter().map(|s| s.swap_with.as_deref()).collect();
+        assert_eq!(swaps, vec![Some("b3")]);
+    }
+
+    #[test]
+    fn suggestions_are_ordered_by_objective_change() {
+        let people = vec![
+            person("b1", "Boys", &["b3", "b5"]),
+            person("b2", "Boys", &[]),
+            person("b3", "Boys", &["b4"]),
+            person("b4", "Boys", &["b3"]),
+            person("b5", "Boys", &[]),
+            person("b6", "Boys", &[]),
+        ];
+        let constraints = constraints(&people, "", 3);
+        let result = result(people, &[
+            ("Boys", vec![vec!["b1", "b2"], vec!["b3", "b4"], vec!["b5", "b6"]], vec![2, 2, 2]),
+        ], &constraints);
+
+        let suggestions = suggest_fixes(&result, "b1", &constraints).unwrap();
+
+        assert!(!suggestions.is_empty());
+        assert!(suggestions.windows(2).all(|pair| pair[0].objective_change >= pair[1].objective_change));
+        assert!(suggestions.iter().all(|s| s.person == "b1"));
+    }
+
+    /// Four boys in two rooms of 2, b1 and b3 choosing each other
+    fn four_boys() -> (SolveResult, Constraints) {
+        let people = vec![
+            person("b1", "Boys", &["b3"]),
+            person("b2", "Boys", &[]),
+            person("b3", "Boys", &["b1"]),
+            person("b4", "Boys", &[]),
+        ];
+        let constraints = constraints(&people, "", 3);
+        let result = result(people, &[
+            ("Boys", vec![vec!["b1", "b2"], vec!["b3", "b4"]], vec![2, 2]),
+        ], &constraints);
+        (result, constraints)
+    }
+
+    #[test]
+    fn swap_rescores_the_rooms() {
+        let (result, constraints) = four_boys();
+        assert_eq!(result.choice_score, 0);
+
+        let edited = swap_people(result, "b2", "b3", &constraints).unwrap();
+
+        assert_eq!(edited.rooms_by_category["Boys"], vec![vec!["b1", "b3"], vec!["b2", "b4"]]);
+        assert!(edited.choice_score > 0);
+        assert!(edited.person_stats["b1"].choices_received > 0);
+        assert!(edited.explanations.is_empty());
+    }
+
+    #[test]
+    fn move_into_a_new_room_plans_it_for_its_occupant() {
+        let (result, constraints) = four_boys();
+
+        let edited = move_person(result, "b2", "Boys", 2, &constraints).unwrap();
+
+        assert_eq!(edited.rooms_by_category["Boys"], vec![vec!["b1"], vec!["b3", "b4"], vec!["b2"]]);
+        assert_eq!(edited.room_sizes["Boys"], vec![2, 2, 1]);
+        assert_eq!(edited.total_rooms, 3);
+    }
+
+    #[test]
+    fn move_that_empties_a_room_drops_its_size_and_overfilling_grows_one() {
+        let (result, constraints) = four_boys();
+        let result = move_person(result, "b2", "Boys", 1, &constraints).unwrap();
+        assert_eq!(result.room_sizes["Boys"], vec![2, 3]);
+
+        let edited = move_person(result, "b1", "Boys", 1, &constraints).unwrap();
+
+        assert_eq!(edited.rooms_by_category["Boys"], vec![vec!["b3", "b4", "b2", "b1"]]);
+        assert_eq!(edited.room_sizes["Boys"], vec![4]);
+        assert!(matches!(edited.violations.as_slice(), [Violation::OverCapacity { room: 0, size: 4, .. }]));
+    }
+
+    #[test]
+    fn move_to_a_room_past_the_end_is_refused() {
+        let (result, constraints) = four_boys();
+
+        assert!(move_person(result.clone(), "b2", "Boys", 3, &constraints).is_err());
+        assert!(move_person(result, "b2", "Girls", 0, &constraints).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use crate::constraints::Constraints;
+use crate::solver::{self, Person, Room, Solution};
+
+/// Pools larger than this are left to the heuristic search
+pub const EXACT_MAX_PEOPLE: usize = 30;
+
+pub const DEFAULT_NODE_BUDGET: usize = 500_000;
+
+pub struct ExactOutcome {
+    /// Best complete assignment found, if any
+    pub solution: Option<Solution>,
+    /// The search finished within its node budget, so `solution` is optimal
+    /// (or, if `None`, no valid assignment exists)
+    pub proven_optimal: bool,
+    pub nodes: usize,
+}
+
+/// Branch-and-bound over every way of splitting one pool into rooms of the given sizes.
+///
+/// People are assigned one at a time. Empty rooms of the same size are interchangeable,
+/// so a person only ever opens the first empty room of each size. A branch is dropped
+/// once an optimistic score for it (every remaining choice satisfied where room is left)
+/// can't beat the best assignment found so far.
+pub fn solve_exact(
+    pool: &str,
+    people: &[Person],
+    sizes: &[usize],
+    constraints: &Constraints,
+    node_budget: usize,
+) -> ExactOutcome {
+    let index_of: HashMap<&str, usize> = people.iter()
+        .enumerate()
+        .map(|(i, p)| (p.id.as_str(), i))
+        .collect();
+
+    // Choices as indices, only those inside the pool can ever be satisfied
+    let choices: Vec<Vec<usize>> = people.iter()
+        .map(|p| p.choices.iter().filter_map(|c| index_of.get(c.as_str()).copied()).collect())
+        .collect();
+
+    // Most connected people first, so rooms fill with friends and bounds tighten early
+    let mut order: Vec<usize> = (0..people.len()).collect();
+    order.sort_by_key(|&i| {
+        let chosen_by = choices.iter().filter(|c| c.contains(&i)).count();
+        std::cmp::Reverse(choices[i].len() + chosen_by)
+    });
+
+    let mut sizes = sizes.to_vec();
+    sizes.sort_by(|a, b| b.cmp(a));
+    let imbalance = match (sizes.first(), sizes.last()) {
+        (Some(max), Some(min)) => max - min,
+        _ => 0,
+    };
+
+    let mut search = Search {
+        people,
+        constraints,
+        choices,
+        order,
+        imbalance,
+        max_size: sizes.first().copied().unwrap_or(0),
+        rooms: sizes.iter().map(|&size| Room::new(pool.to_string(), size)).collect(),
+        room_of: vec![None; people.len()],
+        best: None,
+        best_score: i32::MIN,
+        nodes: 0,
+        node_budget,
+        aborted: false,
+    };
+
+    search.branch(0);
+
+    println!("Exact search for {}: {} nodes, {}", pool, search.nodes,
+             if search.aborted { "node budget exceeded" } else { "proven optimal" });
+
+    ExactOutcome {
+        solution: search.best,
+        proven_optimal: !search.aborted,
+        nodes: search.nodes,
+    }
+}
+
+struct Search<'a> {
+    people: &'a [Person],
+    constraints: &'a Constraints,
+    choices: Vec<Vec<usize>>,
+    order: Vec<usize>,
+    imbalance: usize,
+    max_size: usize,
+    rooms: Vec<Room>,
+    room_of: Vec<Option<usize>>,
+    best: Option<Solution>,
+    best_score: i32,
+    nodes: usize,
+    node_budget: usize,
+    aborted: bool,
+}
+
+impl<'a> Search<'a> {
+    fn branch(&mut self, depth: usize) {
+        if self.aborted {
+            return;
+        }
+        self.nodes += 1;
+        if self.nodes > self.node_budget {
+            self.aborted = true;
+            return;
+        }
+
+        if depth == self.order.len() {
+            let eval = solver::evaluate_solution(&self.rooms, self.people, self.constraints);
+            if eval.score > self.best_score {
+                self.best_score = eval.score;
+                self.best = Some(self.rooms.clone());
+            }
+            return;
+        }
+
+        if self.upper_bound() <= self.best_score {
+            return;
+        }
+
+        let person_idx = self.order[depth];
+        let person = &self.people[person_idx];
+
+        let mut candidates: Vec<(usize, usize)> = Vec::new();
+        let mut opened_sizes: Vec<usize> = Vec::new();
+        for (room_idx, room) in self.rooms.iter().enumerate() {
+            if !room.has_space() {
+                continue;
+            }
+            if room.members.is_empty() {
+                if opened_sizes.contains(&room.max_size) {
+                    continue;
+                }
+                opened_sizes.push(room.max_size);
+            }
+            if !solver::can_add_person_to_room(person, room, self.people, self.constraints) {
+                continue;
+            }
+
+            // Try rooms with the most choices in either direction first
+            let affinity = self.rooms[room_idx].members.iter()
+                .filter_map(|id| self.people.iter().position(|p| &p.id == id))
+                .filter(|&other| self.choices[person_idx].contains(&other) || self.choices[other].contains(&person_idx))
+                .count();
+            candidates.push((room_idx, affinity));
+        }
+        candidates.sort_by_key(|&(_, affinity)| std::cmp::Reverse(affinity));
+
+        for (room_idx, _) in candidates {
+            self.rooms[room_idx].members.push(person.id.clone());
+            self.room_of[person_idx] = Some(room_idx);
+
+            self.branch(depth + 1);
+
+            self.rooms[room_idx].members.pop();
+            self.room_of[person_idx] = None;
+
+            if self.aborted {
+                return;
+            }
+        }
+    }
+
+    /// Optimistic objective for any completion of the current partial assignment
+    fn upper_bound(&self) -> i32 {
+        let mut choice_bound = 0;
+        let mut certainly_without = 0;
+
+        for (i, person) in self.people.iter().enumerate() {
+            let unassigned_choices = self.choices[i].iter()
+                .filter(|&&c| self.room_of[c].is_none())
+                .count();
+
+            let best_case = match self.room_of[i] {
+                Some(room_idx) => {
+                    let room = &self.rooms[room_idx];
+                    let in_room = self.choices[i].iter()
+                        .filter(|&&c| self.room_of[c] == Some(room_idx))
+                        .count();
+                    let slots = room.max_size - room.members.len();
+                    in_room + slots.min(unassigned_choices)
+                }
+                None => {
+                    let reachable = self.choices[i].iter()
+                        .filter(|&&c| match self.room_of[c] {
+                            None => true,
+                            Some(room_idx) => self.rooms[room_idx].has_space(),
+                        })
+                        .count();
+                    reachable.min(self.max_size.saturating_sub(1))
+                }
+            };
+
+            choice_bound += best_case as i32;
+            if !person.choices.is_empty() && best_case == 0 {
+                certainly_without += 1;
+            }
+        }
+
+        if certainly_without == 0 {
+            solver::objective(choice_bound, 0, self.imbalance, 0)
+                .max(solver::objective(choice_bound, 1, self.imbalance, 0))
+        } else {
+            solver::objective(choice_bound, certainly_without, self.imbalance, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds;
+    use crate::testing::{constraints, person};
+
+    fn room_of<'s>(solution: &'s Solution, id: &str) -> &'s Room {
+        solution.iter().find(|room| room.members.iter().any(|m| m == id)).unwrap()
+    }
+
+    #[test]
+    fn pairs_everyone_with_their_mutual_choice() {
+        let people = vec![
+            person("b1", "Boys", &["b2"]),
+            person("b2", "Boys", &["b1"]),
+            person("b3", "Boys", &["b4"]),
+            person("b4", "Boys", &["b3"]),
+        ];
+        let constraints = constraints(&people, "", 2);
+
+        let outcome = solve_exact("Boys", &people, &[2, 2], &constraints, DEFAULT_NODE_BUDGET);
+
+        assert!(outcome.proven_optimal);
+        let solution = outcome.solution.unwrap();
+        assert!(room_of(&solution, "b1").members.contains(&"b2".to_string()));
+        assert!(room_of(&solution, "b3").members.contains(&"b4".to_string()));
+    }
+
+    #[test]
+    fn reaches_the_upper_bound_when_it_can() {
+        let people = vec![
+            person("b1", "Boys", &["b2", "b3"]),
+            person("b2", "Boys", &["b1"]),
+            person("b3", "Boys", &["b1", "b2"]),
+            person("b4", "Boys", &["b5"]),
+            person("b5", "Boys", &["b6"]),
+            person("b6", "Boys", &["b4"]),
+        ];
+        let constraints = constraints(&people, "", 3);
+
+        let outcome = solve_exact("Boys", &people, &[3, 3], &constraints, DEFAULT_NODE_BUDGET);
+
+        let eval = solver::evaluate_solution(&outcome.solution.unwrap(), &people, &constraints);
+        assert_eq!(eval.choice_score, bounds::choice_score_upper_bound(&people, &[3, 3], &constraints));
+    }
+
+    #[test]
+    fn proves_there_is_no_assignment_when_everyone_would_break_an_avoid() {
+        let mut people = vec![person("b1", "Boys", &[]), person("b2", "Boys", &[])];
+        people[0].avoids.push("b2".to_string());
+        let constraints = constraints(&people, "", 2);
+
+        let outcome = solve_exact("Boys", &people, &[2], &constraints, DEFAULT_NODE_BUDGET);
+
+        assert!(outcome.solution.is_none());
+        assert!(outcome.proven_optimal);
+    }
+
+    #[test]
+    fn running_out_of_nodes_proves_nothing() {
+        let people: Vec<Person> = (1..=6).map(|i| person(&format!("b{}", i), "Boys", &[])).collect();
+        let constraints = constraints(&people, "", 3);
+
+        let outcome = solve_exact("Boys", &people, &[3, 3], &constraints, 2);
+
+        assert!(!outcome.proven_optimal);
+        assert!(outcome.nodes <= 3);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::constraints::Constraints;
+use crate::solver::{Person, Room, SolveResult};
+
+/// Why one of an unhappy person's choices isn't in their room; the first that applies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChoiceObstacle {
+    /// The choice names no one in the spreadsheet, e.g. in a result saved before they left
+    UnknownPerson { choice: String },
+    /// The choice's category may not share a room with theirs
+    OtherCategory { choice: String, category: String },
+    /// They avoid their choice
+    Avoids { choice: String },
+    /// Their choice avoids them
+    AvoidedBy { choice: String },
+    /// Their choice is in another pool of rooms, e.g. staff spread across pools
+    OtherPool { choice: String, pool: String },
+    /// Someone in the choice's room avoids them, is avoided by them or may not share with them
+    RoommateConflict { choice: String, roommate: String },
+    /// The choice's room is full and everyone in it has a mutual choice there
+    FullOfMutualPairs { choice: String },
+    /// The choice's room is full
+    RoomFull { choice: String },
+    /// A hard room rule or the leader policy keeps them out of the choice's room
+    RoomRule { choice: String },
+    /// Nothing rules it out; it would cost more elsewhere than it gains
+    Tradeoff { choice: String },
+}
+
+impl ChoiceObstacle {
+    /// One line for the PDF, with names looked up in `people`
+    pub fn describe(&self, people: &[Person]) -> String {
+        let name = |id: &str| people.iter()
+            .find(|p| p.id == id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| id.to_string());
+        match self {
+            ChoiceObstacle::UnknownPerson { choice } => format!("{} isn't in the spreadsheet", choice),
+            ChoiceObstacle::OtherCategory { choice, category } =>
+                format!("{} is in {}, which can't share a room with them", name(choice), category),
+            ChoiceObstacle::Avoids { choice } => format!("they also avoid {}", name(choice)),
+            ChoiceObstacle::AvoidedBy { choice } => format!("{} avoids them", name(choice)),
+            ChoiceObstacle::OtherPool { choice, pool } => format!("{} is in the {} rooms", name(choice), pool),
+            ChoiceObstacle::RoommateConflict { choice, roommate } =>
+                format!("{} rooms with {}, who can't share a room with them", name(choice), name(roommate)),
+            ChoiceObstacle::FullOfMutualPairs { choice } =>
+                format!("{}'s room is full of people who chose each other", name(choice)),
+            ChoiceObstacle::RoomFull { choice } => format!("{}'s room is full", name(choice)),
+            ChoiceObstacle::RoomRule { choice } => format!("a room rule keeps them out of {}'s room", name(choice)),
+            ChoiceObstacle::Tradeoff { choice } =>
+                format!("rooming with {} would cost other people more than it gains", name(choice)),
+        }
+    }
+}
+
+/// Where someone is: pool, room index and the room's members
+type Placement<'a> = (&'a str, usize, &'a [String]);
+
+/// For everyone who made choices but got none, why each choice isn't in their room.
+/// Keyed by person ID.
+pub fn explain_unhappy(result: &SolveResult, constraints: &Constraints) -> HashMap<String, Vec<ChoiceObstacle>> {
+    let people = &result.people;
+    let by_id: HashMap<&str, &Person> = people.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut placement: HashMap<&str, Placement> = HashMap::new();
+    for (category, rooms) in &result.rooms_by_category {
+        for (room_idx, room) in rooms.iter().enumerate() {
+            for id in room {
+                placement.insert(id.as_str(), (category.as_str(), room_idx, room.as_slice()));
+            }
+        }
+    }
+    let mut explanations = HashMap::new();
+
+    for person in people {
+        let Some(&(own_pool, _, own_room)) = placement.get(person.id.as_str()) else {
+            continue;
+        };
+        if person.choices.is_empty() || person.choices.iter().any(|c| own_room.contains(c)) {
+            continue;
+        }
+
+        let obstacles = person.choices.iter()
+            .filter_map(|choice| {
+                let Some(chosen) = by_id.get(choice.as_str()) else {
+                    return Some(ChoiceObstacle::UnknownPerson { choice: choice.clone() });
+                };
+                let &(pool, room_idx, room) = placement.get(choice.as_str())?;
+                let size = result.planned_size(pool, room_idx).unwrap_or(constraints.max_room_size);
+                Some(obstacle(person, chosen, own_pool, (pool, size, room), &by_id, people, constraints))
+            })
+            .collect();
+        explanations.insert(person.id.clone(), obstacles);
+    }
+
+    explanations
+}
+
+/// The first reason `person` isn't with `chosen`, who is in `room` of `pool`, planned for `size`
+fn obstacle(
+    person: &Person,
+    chosen: &Person,
+    own_pool: &str,
+    (pool, size, room): (&str, usize, &[String]),
+    by_id: &HashMap<&str, &Person>,
+    people: &[Person],
+    constraints: &Constraints,
+) -> ChoiceObstacle {
+    let choice = chosen.id.clone();
+
+    if !constraints.compatible(&person.category, &chosen.category) {
+        return ChoiceObstacle::OtherCategory { choice, category: chosen.category.clone() };
+    }
+    if person.avoids.contains(&chosen.id) {
+        return ChoiceObstacle::Avoids { choice };
+    }
+    if chosen.avoids.contains(&person.id) {
+        return ChoiceObstacle::AvoidedBy { choice };
+    }
+    if pool != own_pool {
+        return ChoiceObstacle::OtherPool { choice, pool: pool.to_string() };
+    }
+
+    let conflict = room.iter()
+        .filter(|id| **id != chosen.id)
+        .filter_map(|id| by_id.get(id.as_str()))
+        .find(|roommate| person.avoids.contains(&roommate.id)
+            || roommate.avoids.contains(&person.id)
+            || !constraints.compatible(&person.category, &roommate.category));
+    if let Some(roommate) = conflict {
+        return ChoiceObstacle::RoommateConflict { choice, roommate: roommate.id.clone() };
+    }
+
+    if size > 0 && room.len() >= size {
+        let all_paired = room.iter()
+            .filter_map(|id| by_id.get(id.as_str()))
+            .all(|member| member.choices.iter().any(|c| {
+                room.contains(c) && by_id.get(c.as_str()).is_some_and(|other| other.choices.contains(&member.id))
+            }));
+        return if all_paired {
+            ChoiceObstacle::FullOfMutualPairs { choice }
+        } else {
+            ChoiceObstacle::RoomFull { choice }
+        };
+    }
+
+    let target = Room {
+        category: pool.to_string(),
+        members: room.to_vec(),
+        max_size: size.max(room.len() + 1),
+    };
+    if !constraints.allows(person, &target, people) {
+        return ChoiceObstacle::RoomRule { choice };
+    }
+
+    ChoiceObstacle::Tradeoff { choice }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, result, staff_example};
+
+    /// Six boys in two rooms planned for 3 each, under a maximum of 4
+    fn boys(choices: &[(&str, &[&str])]) -> Vec<Person> {
+        (1..=6)
+            .map(|i| {
+                let id = format!("b{}", i);
+                let chose = choices.iter().find(|(who, _)| *who == id).map_or(&[][..], |(_, c)| c);
+                person(&id, "Boys", chose)
+            })
+            .collect()
+    }
+
+    fn explain(people: Vec<Person>, rooms: Vec<Vec<&str>>, sizes: Vec<usize>) -> HashMap<String, Vec<ChoiceObstacle>> {
+        let constraints = constraints(&people, "", 4);
+        result(people, &[("Boys", rooms, sizes)], &constraints).explanations
+    }
+
+    #[test]
+    fn room_at_its_planned_size_is_full_below_the_maximum() {
+        let people = boys(&[("b4", &["b1"]), ("b1", &["b2"]), ("b2", &["b1"])]);
+        let explanations = explain(people, vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "b6"]], vec![3, 3]);
+
+        assert_eq!(explanations["b4"], vec![ChoiceObstacle::RoomFull { choice: "b1".to_string() }]);
+    }
+
+    #[test]
+    fn full_room_of_mutual_choices_says_so() {
+        let people = boys(&[("b4", &["b1"]), ("b1", &["b2", "b3"]), ("b2", &["b1"]), ("b3", &["b1"])]);
+        let explanations = explain(people, vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "b6"]], vec![3, 3]);
+
+        assert_eq!(explanations["b4"], vec![ChoiceObstacle::FullOfMutualPairs { choice: "b1".to_string() }]);
+    }
+
+    #[test]
+    fn room_below_its_planned_size_is_a_tradeoff() {
+        let people = boys(&[("b4", &["b1"])]);
+        let explanations = explain(people, vec![vec!["b1", "b2"], vec!["b3", "b4", "b5", "b6"]], vec![3, 4]);
+
+        assert_eq!(explanations["b4"], vec![ChoiceObstacle::Tradeoff { choice: "b1".to_string() }]);
+    }
+
+    #[test]
+    fn avoidance_comes_before_room_size() {
+        let mut people = boys(&[("b4", &["b1"])]);
+        people[0].avoids.push("b4".to_string());
+        let explanations = explain(people, vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "b6"]], vec![3, 3]);
+
+        assert_eq!(explanations["b4"], vec![ChoiceObstacle::AvoidedBy { choice: "b1".to_string() }]);
+    }
+
+    #[test]
+    fn staff_choice_in_another_pool_names_that_pool() {
+        let mut people = staff_example();
+        people[11].choices.push("b1".to_string());
+        let constraints = constraints(&people, "Staff, *", 4);
+        let result = result(people, &[
+            ("Boys + Staff", vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "s1"]], vec![3, 3]),
+            ("Girls + Staff", vec![vec!["g1", "g2", "g3"], vec!["g4", "g5", "s2"]], vec![3, 3]),
+        ], &constraints);
+
+        assert_eq!(result.explanations["s2"], vec![ChoiceObstacle::OtherPool {
+            choice: "b1".to_string(),
+            pool: "Boys + Staff".to_string(),
+        }]);
+        assert!(!result.explanations.contains_key("b4"));
+    }
+
+    #[test]
+    fn people_with_a_choice_or_no_choices_need_no_explanation() {
+        let people = boys(&[("b1", &["b2"])]);
+        let explanations = explain(people, vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "b6"]], vec![3, 3]);
+
+        assert!(explanations.is_empty());
+    }
+
+    #[test]
+    fn choice_of_someone_not_in_the_spreadsheet_says_so() {
+        let people = boys(&[("b4", &["zed", "b1"])]);
+        let explanations = explain(people.clone(), vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "b6"]], vec![3, 3]);
+
+        let obstacles = &explanations["b4"];
+        assert_eq!(obstacles, &vec![
+            ChoiceObstacle::UnknownPerson { choice: "zed".to_string() },
+            ChoiceObstacle::RoomFull { choice: "b1".to_string() },
+        ]);
+        assert_eq!(obstacles[0].describe(&people), "zed isn't in the spreadsheet");
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rayon::iter::Either;
+use rayon::prelude::*;
+use crate::constraints::Constraints;
+use crate::construction::{self, SolutionBuilder};
+use crate::solver::{self, Evaluation, Person, PoolOutcome, Room, RoomDistribution, SearchProgress, Solution, StopReason, Strategy};
+
+pub const DEFAULT_POPULATION: usize = 60;
+pub const DEFAULT_GENERATIONS: usize = 300;
+
+/// Best solutions carried unchanged into the next generation
+const ELITE: usize = 2;
+/// Parents are the best of this many picked at random
+const TOURNAMENT_SIZE: usize = 3;
+/// Swaps tried on each child after crossover
+const MUTATION_SWAPS: usize = 4;
+
+type Scored = (Solution, Evaluation);
+
+/// Genetic search for one pool of rooms.
+///
+/// The first generation comes from the same greedy construction the random search uses,
+/// plus the pool's best so far in `progress` (e.g. starting rooms from an earlier run).
+/// Each child then takes whole rooms from two parents, best rooms first, and the people
+/// left over are placed by the greedy phase 2. A few swaps that don't lose any satisfied
+/// choices are tried on every child before it joins the next generation. Stops early
+/// when `progress` says to, counting children built as iterations.
+pub fn evolve(
+    pool: &str,
+    people: &[Person],
+    distribution: &RoomDistribution,
+    constraints: &Constraints,
+    population_size: usize,
+    generations: usize,
+    progress: &SearchProgress,
+) -> PoolOutcome {
+    let population_size = population_size.max(ELITE + 1);
+    let builder = SolutionBuilder::new(pool, people, distribution, constraints).pairing(progress.pairing());
+
+    let (mut population, stuck_on): (Vec<Scored>, Vec<String>) = (0..population_size)
+        .into_par_iter()
+        .map(|seed| builder.build(seed))
+        .partition_map(|built| match built {
+            Ok(solution) => {
+                let eval = solver::evaluate_solution(&solution, people, constraints);
+                Either::Left((solution, eval))
+            }
+            Err(id) => Either::Right(id),
+        });
+    progress.constructed(population.len(), &stuck_on);
+    progress.advance(population_size);
+    let mut built = population_size;
+
+    if let Some((solution, eval, _)) = progress.best(pool) {
+        population.push((solution, eval));
+    }
+
+    println!("{}: genetic search with {} of {} starting solutions valid, {} generations",
+             pool, population.len(), population_size, generations);
+
+    if population.is_empty() {
+        return PoolOutcome { best: None, iterations: built, strategy: Strategy::Genetic, stop_reason: StopReason::Finished };
+    }
+    sort_best_first(&mut population);
+    let mut best = population[0].clone();
+    progress.improved(pool, Strategy::Genetic, built, &best.0, &best.1);
+    let mut since_improvement = 0;
+    let mut stop_reason = StopReason::Finished;
+
+    for generation in 1..=generations {
+        if let Some(reason) = progress.stop_reason(Some(&best.1), since_improvement) {
+            println!("  {}: stopping after {} generations ({:?})", pool, generation - 1, reason);
+            stop_reason = reason;
+            break;
+        }
+
+        let (children, stuck_on): (Vec<Scored>, Vec<String>) = (0..population_size - ELITE)
+            .into_par_iter()
+            .map(|i| -> Result<Scored, String> {
+                let mut rng = StdRng::seed_from_u64((generation * population_size + i) as u64);
+                let mother = tournament(&population, &mut rng);
+                let father = tournament(&population, &mut rng);
+
+                let mut child = crossover(mother, father, people, constraints, &mut rng)?;
+                mutate(&mut child, people, constraints, &mut rng);
+
+                let eval = solver::evaluate_solution(&child, people, constraints);
+                Ok((child, eval))
+            })
+            .partition_map(|child| match child {
+                Ok(scored) => Either::Left(scored),
+                Err(id) => Either::Right(id),
+            });
+        progress.constructed(children.len(), &stuck_on);
+        progress.advance(population_size - ELITE);
+        built += population_size - ELITE;
+        since_improvement += population_size - ELITE;
+
+        // Elites, then every child, topped up from the old population when crossover failed
+        let survivors = population_size.saturating_sub(ELITE + children.len());
+        let mut next: Vec<Scored> = population.iter().take(ELITE + survivors).cloned().collect();
+        next.extend(children);
+        sort_best_first(&mut next);
+        population = next;
+
+        if population[0].1.score > best.1.score {
+            best = population[0].clone();
+            let Evaluation { score, choice_score, without_choices, imbalance, .. } = best.1;
+            println!("  {} generation {}: score={}, choice_score={}, imbalance={}, without_choices={}",
+                     pool, generation, score, choice_score, imbalance, without_choices);
+            progress.improved(pool, Strategy::Genetic, built, &best.0, &best.1);
+            since_improvement = 0;
+        }
+    }
+
+    PoolOutcome { best: Some(best), iterations: built, strategy: Strategy::Genetic, stop_reason }
+}
+
+fn sort_best_first(population: &mut [Scored]) {
+    population.sort_by_key(|(_, eval)| std::cmp::Reverse(eval.score));
+}
+
+fn tournament<'a>(population: &'a [Scored], rng: &mut StdRng) -> &'a Solution {
+    (0..TOURNAMENT_SIZE)
+        .map(|_| &population[rng.gen_range(0..population.len())])
+        .max_by_key(|(_, eval)| eval.score)
+        .map(|(solution, _)| solution)
+        .expect("population is never empty")
+}
+
+/// Choices satisfied inside one room, per member
+fn room_quality(room: &Room, people: &[Person]) -> f64 {
+    if room.members.is_empty() {
+        return 0.0;
+    }
+    let satisfied = room_choices(room, people);
+    satisfied as f64 / room.members.len() as f64
+}
+
+fn room_choices(room: &Room, people: &[Person]) -> usize {
+    room.members.iter()
+        .filter_map(|id| people.iter().find(|p| &p.id == id))
+        .map(|person| person.choices.iter().filter(|c| room.members.contains(c)).count())
+        .sum()
+}
+
+/// Builds a child from whole rooms of both parents. Rooms are taken best first (with a
+/// little noise so children differ), skipping any that would place someone twice or
+/// has no matching empty slot. Copied rooms were valid in their parent, so only the
+/// people left over need checking, which the greedy phase 2 does; it fails with the ID
+/// of anyone who can't be placed.
+fn crossover(
+    mother: &Solution,
+    father: &Solution,
+    people: &[Person],
+    constraints: &Constraints,
+    rng: &mut StdRng,
+) -> Result<Solution, String> {
+    let mut parent_rooms: Vec<(&Room, f64)> = mother.iter()
+        .chain(father.iter())
+        .map(|room| (room, room_quality(room, people) + rng.gen::<f64>() * 0.5))
+        .collect();
+    parent_rooms.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut child: Solution = mother.iter()
+        .map(|room| Room::new(room.category.clone(), room.max_size))
+        .collect();
+    let mut placed: HashSet<String> = HashSet::new();
+
+    for (room, _) in parent_rooms {
+        if room.members.iter().any(|id| placed.contains(id)) {
+            continue;
+        }
+        let Some(slot) = child.iter_mut()
+            .find(|slot| slot.members.is_empty() && slot.max_size == room.max_size) else {
+            continue;
+        };
+        slot.members.extend(room.members.iter().cloned());
+        placed.extend(room.members.iter().cloned());
+    }
+
+    construction::place_remaining(&mut child, people, constraints, &mut placed, rng)?;
+    Ok(child)
+}
+
+/// Tries swapping random pairs of people between rooms, keeping a swap only if both
+/// still fit and the two rooms satisfy at least as many choices as before
+fn mutate(solution: &mut Solution, people: &[Person], constraints: &Constraints, rng: &mut StdRng) {
+    if solution.len() < 2 {
+        return;
+    }
+
+    for _ in 0..MUTATION_SWAPS {
+        let mut rooms: Vec<usize> = (0..solution.len()).collect();
+        rooms.shuffle(rng);
+        let (a, b) = (rooms[0], rooms[1]);
+        if solution[a].members.is_empty() || solution[b].members.is_empty() {
+            continue;
+        }
+
+        let before = room_choices(&solution[a], people) + room_choices(&solution[b], people);
+        let i = rng.gen_range(0..solution[a].members.len());
+        let j = rng.gen_range(0..solution[b].members.len());
+
+        let id_a = solution[a].members.remove(i);
+        let id_b = solution[b].members.remove(j);
+        let (Some(person_a), Some(person_b)) = (
+            people.iter().find(|p| p.id == id_a),
+            people.iter().find(|p| p.id == id_b),
+        ) else {
+            solution[a].members.insert(i, id_a);
+            solution[b].members.insert(j, id_b);
+            continue;
+        };
+
+        let fits = solver::can_add_person_to_room(person_a, &solution[b], people, constraints)
+            && solver::can_add_person_to_room(person_b, &solution[a], people, constraints);
+        if fits {
+            solution[a].members.insert(i, id_b.clone());
+            solution[b].members.insert(j, id_a.clone());
+            let after = room_choices(&solution[a], people) + room_choices(&solution[b], people);
+            if after >= before {
+                continue;
+            }
+            solution[a].members.remove(i);
+            solution[b].members.remove(j);
+        }
+
+        solution[a].members.insert(i, id_a);
+        solution[b].members.insert(j, id_b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::SolveJobs;
+    use crate::solver::SolveOptions;
+    use crate::testing::{assert_everyone_placed_once, constraints, ring_of_boys};
+
+    #[test]
+    fn evolving_places_everyone_once_and_never_loses_the_seed() {
+        let people = ring_of_boys(12);
+        let constraints = constraints(&people, "", 4);
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let seed = SolutionBuilder::new("Boys", &people, &distribution, &constraints).build(1).unwrap();
+        let seed_eval = solver::evaluate_solution(&seed, &people, &constraints);
+        let job = SolveJobs::default().start();
+        let progress = SearchProgress::unreported(&job, 1_000, &SolveOptions::default());
+        progress.improved("Boys", Strategy::RandomSearch, 0, &seed, &seed_eval);
+
+        let outcome = evolve("Boys", &people, &distribution, &constraints, 20, 30, &progress);
+        let (solution, eval) = outcome.best.unwrap();
+
+        assert_everyone_placed_once(&solution, &people);
+        assert!(eval.score >= seed_eval.score, "{} < {}", eval.score, seed_eval.score);
+        assert_eq!(eval.score, solver::evaluate_solution(&solution, &people, &constraints).score);
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Instant;
+use serde::Serialize;
+use crate::solver::SolveResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Finished,
+    /// Stopped by `cancel_solve`; the result holds the best found before it stopped
+    Cancelled,
+    Failed,
+}
+
+/// What `get_solve_status` and the `solve_finished` event report about a job
+#[derive(Clone, Serialize)]
+pub struct JobStatus {
+    pub job_id: u64,
+    pub state: JobState,
+    pub percent: u32,
+    pub result: Option<SolveResult>,
+    pub error: Option<String>,
+}
+
+/// One solve running in the background, shared by its worker thread and the commands
+/// that query or cancel it
+pub struct SolveJob {
+    pub id: u64,
+    /// When the solve was asked for; time limits count from here
+    pub started: Instant,
+    cancelled: AtomicBool,
+    percent: AtomicU32,
+    outcome: Mutex<Option<Result<SolveResult, String>>>,
+}
+
+impl SolveJob {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_percent(&self, percent: u32) {
+        self.percent.store(percent, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self, outcome: Result<SolveResult, String>) {
+        *self.outcome.lock().unwrap() = Some(outcome);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.outcome.lock().unwrap().is_some()
+    }
+
+    pub fn status(&self) -> JobStatus {
+        let outcome = self.outcome.lock().unwrap();
+        let (state, result, error) = match &*outcome {
+            None => (JobState::Running, None, None),
+            Some(Err(e)) => (JobState::Failed, None, Some(e.clone())),
+            Some(Ok(result)) if self.is_cancelled() => (JobState::Cancelled, Some(result.clone()), None),
+            Some(Ok(result)) => (JobState::Finished, Some(result.clone()), None),
+        };
+        JobStatus {
+            job_id: self.id,
+            state,
+            percent: self.percent.load(Ordering::Relaxed),
+            result,
+            error,
+        }
+    }
+}
+
+/// Every solve started this session, kept as Tauri state
+#[derive(Default)]
+pub struct SolveJobs {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, Arc<SolveJob>>>,
+}
+
+impl SolveJobs {
+    /// Registers a new job, forgetting finished ones except the latest, whose result can
+    /// still be fetched
+    pub fn start(&self) -> Arc<SolveJob> {
+        let job = Arc::new(SolveJob {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            started: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            percent: AtomicU32::new(0),
+            outcome: Mutex::new(None),
+        });
+        let mut jobs = self.jobs.lock().unwrap();
+        let latest_finished = jobs.values().filter(|other| other.is_finished()).map(|other| other.id).max();
+        jobs.retain(|id, other| !other.is_finished() || Some(*id) == latest_finished);
+        jobs.insert(job.id, Arc::clone(&job));
+        job
+    }
+
+    pub fn get(&self, job_id: u64) -> Result<Arc<SolveJob>, String> {
+        self.jobs.lock().unwrap()
+            .get(&job_id)
+            .cloned()
+            .ok_or_else(|| format!("No solve with ID {}", job_id))
+    }
+
+    /// Asks a running solve to stop; it finishes with the best rooms found so far
+    pub fn cancel(&self, job_id: u64) -> Result<(), String> {
+        self.get(job_id)?.cancelled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, result};
+
+    fn solved() -> SolveResult {
+        let people = vec![person("b1", "Boys", &[])];
+        let constraints = constraints(&people, "", 4);
+        result(people, &[("Boys", vec![vec!["b1"]], vec![1])], &constraints)
+    }
+
+    #[test]
+    fn jobs_run_until_finished_or_failed() {
+        let jobs = SolveJobs::default();
+        let first = jobs.start();
+        let second = jobs.start();
+        assert_eq!((first.id, second.id), (1, 2));
+        assert_eq!(jobs.get(1).unwrap().status().state, JobState::Running);
+
+        first.set_percent(40);
+        first.finish(Ok(solved()));
+        let status = jobs.get(1).unwrap().status();
+        assert_eq!((status.state, status.percent), (JobState::Finished, 40));
+        assert!(status.result.is_some() && status.error.is_none());
+
+        second.finish(Err("No valid room assignment exists for Boys".to_string()));
+        let status = jobs.get(2).unwrap().status();
+        assert_eq!(status.state, JobState::Failed);
+        assert_eq!(status.error.as_deref(), Some("No valid room assignment exists for Boys"));
+    }
+
+    #[test]
+    fn cancelled_job_keeps_its_best_result() {
+        let jobs = SolveJobs::default();
+        let job = jobs.start();
+        jobs.cancel(job.id).unwrap();
+        assert!(job.is_cancelled());
+        assert_eq!(job.status().state, JobState::Running);
+
+        job.finish(Ok(solved()));
+        let status = jobs.get(job.id).unwrap().status();
+        assert_eq!(status.state, JobState::Cancelled);
+        assert!(status.result.is_some());
+        assert_eq!(jobs.cancel(99).unwrap_err(), "No solve with ID 99");
+    }
+
+    #[test]
+    fn starting_a_job_keeps_the_latest_finished_one() {
+        let jobs = SolveJobs::default();
+        let older = jobs.start();
+        older.finish(Ok(solved()));
+        let latest = jobs.start();
+        latest.finish(Ok(solved()));
+        let running = jobs.start();
+
+        jobs.start();
+        assert_eq!(jobs.get(latest.id).unwrap().status().state, JobState::Finished);
+        assert_eq!(jobs.get(running.id).unwrap().status().state, JobState::Running);
+        assert_eq!(jobs.get(older.id).err(), Some(format!("No solve with ID {}", older.id)));
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use crate::constraints::Constraints;
+use crate::construction::SolutionBuilder;
+use crate::solver::{self, Evaluation, Person, PoolOutcome, Room, RoomDistribution, SearchProgress, Solution, StopReason, Strategy};
+
+/// Moves tried from each starting point before starting again
+pub const MOVES_PER_RESTART: usize = 2_000;
+
+/// Seeds tried for a greedy starting point before a restart gives up
+const START_ATTEMPTS: usize = 20;
+
+/// Hill climbing for one pool of rooms, spending `num_moves` in total.
+///
+/// Restarts run in parallel. Even restarts begin from the best solution found for the
+/// pool so far by any strategy (or the starting rooms), so when racing in a portfolio
+/// this polishes the other strategies' results too; odd ones, and even ones with nothing
+/// to start from, begin from a fresh greedy construction. Restarts are skipped once
+/// `progress` says to stop, with stalling measured against the pool's shared best.
+pub fn search(
+    pool: &str,
+    people: &[Person],
+    distribution: &RoomDistribution,
+    constraints: &Constraints,
+    num_moves: usize,
+    first_seed: usize,
+    progress: &SearchProgress,
+) -> PoolOutcome {
+    let builder = SolutionBuilder::new(pool, people, distribution, constraints).pairing(progress.pairing());
+    let restarts = num_moves.div_ceil(MOVES_PER_RESTART);
+    let since_improvement = AtomicUsize::new(0);
+    let stopped = OnceLock::new();
+
+    let climbs: Vec<(Solution, Evaluation, usize)> = (0..restarts)
+        .into_par_iter()
+        .filter_map(|restart| {
+            let best = progress.best_eval(pool);
+            if let Some(reason) = progress.stop_reason(best.as_ref(), since_improvement.load(Ordering::Relaxed)) {
+                let _ = stopped.set(reason);
+                return None;
+            }
+
+            let seed = first_seed + restart;
+            let shared = if restart % 2 == 0 { progress.best(pool) } else { None };
+            let start = match shared {
+                Some((solution, _, _)) => solution,
+                None => {
+                    let mut stuck_on = Vec::new();
+                    let built = (0..START_ATTEMPTS).find_map(|attempt| {
+                        builder.build(seed * START_ATTEMPTS + attempt)
+                            .map_err(|id| stuck_on.push(id))
+                            .ok()
+                    });
+                    progress.constructed(built.is_some() as usize, &stuck_on);
+                    built?
+                }
+            };
+
+            let moves = MOVES_PER_RESTART.min(num_moves - restart * MOVES_PER_RESTART);
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let (solution, eval) = climb(start, people, constraints, moves, &mut rng);
+
+            progress.advance(moves);
+            if progress.improved(pool, Strategy::LocalSearch, (seed + 1) * MOVES_PER_RESTART, &solution, &eval) {
+                since_improvement.store(0, Ordering::Relaxed);
+            } else {
+                since_improvement.fetch_add(moves, Ordering::Relaxed);
+            }
+            Some((solution, eval, moves))
+        })
+        .collect();
+
+    let iterations = climbs.iter().map(|(_, _, moves)| moves).sum();
+    let best = climbs.into_iter()
+        .max_by_key(|(_, eval, _)| eval.score)
+        .map(|(solution, eval, _)| (solution, eval));
+
+    PoolOutcome {
+        best,
+        iterations,
+        strategy: Strategy::LocalSearch,
+        stop_reason: stopped.into_inner().unwrap_or(StopReason::Finished),
+    }
+}
+
+/// Tries `moves` random changes, each moving someone to a room with space or swapping
+/// two people, and keeps every change that leaves the objective no worse. Equal moves
+/// are kept so the search can drift across plateaus. Only the two rooms a change
+/// touches are rescored.
+pub fn climb(
+    mut solution: Solution,
+    people: &[Person],
+    constraints: &Constraints,
+    moves: usize,
+    rng: &mut StdRng,
+) -> (Solution, Evaluation) {
+    let mut eval = solver::evaluate_solution(&solution, people, constraints);
+    if solution.len() < 2 {
+        return (solution, eval);
+    }
+    let by_id: HashMap<&str, &Person> = people.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut room_scores: Vec<RoomScore> = solution.iter()
+        .map(|room| RoomScore::of(room, &by_id, people, constraints))
+        .collect();
+
+    for _ in 0..moves {
+        let from = rng.gen_range(0..solution.len());
+        let to = rng.gen_range(0..solution.len());
+        if from == to || solution[from].members.is_empty() {
+            continue;
+        }
+
+        let i = rng.gen_range(0..solution[from].members.len());
+        let Some(&person) = by_id.get(solution[from].members[i].as_str()) else {
+            continue;
+        };
+
+        // Move when there's room, otherwise swap with someone in the other room
+        let swap_with = if solution[to].has_space() && rng.gen_bool(0.5) {
+            None
+        } else if solution[to].members.is_empty() {
+            continue;
+        } else {
+            Some(rng.gen_range(0..solution[to].members.len()))
+        };
+
+        let moved = solution[from].members.remove(i);
+        let swapped = swap_with.map(|j| solution[to].members.remove(j));
+
+        let other = swapped.as_ref().and_then(|id| by_id.get(id.as_str()));
+        let fits = solver::can_add_person_to_room(person, &solution[to], people, constraints)
+            && match other {
+                Some(other) => solver::can_add_person_to_room(other, &solution[from], people, constraints),
+                None => true,
+            };
+
+        if fits {
+            solution[to].members.push(moved.clone());
+            if let Some(id) = &swapped {
+                solution[from].members.push(id.clone());
+            }
+
+            let from_score = RoomScore::of(&solution[from], &by_id, people, constraints);
+            let to_score = RoomScore::of(&solution[to], &by_id, people, constraints);
+            let (old_from, old_to) = (&room_scores[from], &room_scores[to]);
+            let choice_score = eval.choice_score - old_from.choice_score - old_to.choice_score
+                + from_score.choice_score + to_score.choice_score;
+            let without_choices = eval.without_choices - old_from.without_choices - old_to.without_choices
+                + from_score.without_choices + to_score.without_choices;
+            let penalty = eval.penalty - old_from.penalty - old_to.penalty + from_score.penalty + to_score.penalty;
+            let imbalance = solver::calculate_imbalance(&solution);
+            let score = solver::objective(choice_score, without_choices, imbalance, penalty);
+            if score >= eval.score {
+                eval = Evaluation { score, choice_score, without_choices, imbalance, penalty };
+                room_scores[from] = from_score;
+                room_scores[to] = to_score;
+                continue;
+            }
+
+            solution[to].members.pop();
+            if swapped.is_some() {
+                solution[from].members.pop();
+            }
+        }
+
+        // Put everyone back where they were
+        solution[from].members.insert(i, moved);
+        if let (Some(j), Some(id)) = (swap_with, swapped) {
+            solution[to].members.insert(j, id);
+        }
+    }
+
+    (solution, eval)
+}
+
+/// The parts of the objective that depend on one room alone
+struct RoomScore {
+    choice_score: i32,
+    without_choices: usize,
+    penalty: i32,
+}
+
+impl RoomScore {
+    fn of(room: &Room, by_id: &HashMap<&str, &Person>, people: &[Person], constraints: &Constraints) -> RoomScore {
+        let mut choice_score = 0;
+        let mut without_choices = 0;
+        for id in &room.members {
+            let Some(person) = by_id.get(id.as_str()) else {
+                continue;
+            };
+            let satisfied = person.choices.iter().filter(|c| room.members.contains(c)).count();
+            choice_score += satisfied as i32;
+            if !person.choices.is_empty() && satisfied == 0 {
+                without_choices += 1;
+            }
+        }
+        RoomScore {
+            choice_score,
+            without_choices,
+            penalty: constraints.soft_penalty(std::slice::from_ref(room), people),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::SolveJobs;
+    use crate::solver::SolveOptions;
+    use crate::testing::{assert_everyone_placed_once, constraints, ring_of_boys};
+
+    #[test]
+    fn climbing_places_everyone_once_and_keeps_the_score_it_reports() {
+        let mut people = ring_of_boys(12);
+        people[0].prefer_not.push("b3".to_string());
+        let mut constraints = constraints(&people, "", 4);
+        constraints.prefer_not_penalty = 5;
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let seed = SolutionBuilder::new("Boys", &people, &distribution, &constraints).build(1).unwrap();
+        let seed_eval = solver::evaluate_solution(&seed, &people, &constraints);
+
+        let (solution, eval) = climb(seed, &people, &constraints, 2_000, &mut StdRng::seed_from_u64(7));
+        let rescored = solver::evaluate_solution(&solution, &people, &constraints);
+
+        assert_everyone_placed_once(&solution, &people);
+        assert!(eval.score >= seed_eval.score, "{} < {}", eval.score, seed_eval.score);
+        assert_eq!((eval.score, eval.choice_score, eval.without_choices, eval.imbalance, eval.penalty),
+                   (rescored.score, rescored.choice_score, rescored.without_choices, rescored.imbalance, rescored.penalty));
+    }
+
+    #[test]
+    fn search_places_everyone_once_and_never_loses_the_seed() {
+        let people = ring_of_boys(12);
+        let constraints = constraints(&people, "", 4);
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let seed = SolutionBuilder::new("Boys", &people, &distribution, &constraints).build(1).unwrap();
+        let seed_eval = solver::evaluate_solution(&seed, &people, &constraints);
+        let job = SolveJobs::default().start();
+        let progress = SearchProgress::unreported(&job, 4 * MOVES_PER_RESTART, &SolveOptions::default());
+        progress.improved("Boys", Strategy::RandomSearch, 0, &seed, &seed_eval);
+
+        let outcome = search("Boys", &people, &distribution, &constraints, 4 * MOVES_PER_RESTART, 0, &progress);
+        let (solution, eval) = outcome.best.unwrap();
+
+        assert_everyone_placed_once(&solution, &people);
+        assert!(eval.score >= seed_eval.score, "{} < {}", eval.score, seed_eval.score);
+        assert_eq!(outcome.iterations, 4 * MOVES_PER_RESTART);
+    }
+}
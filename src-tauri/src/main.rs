@@ -2,21 +2,121 @@
 
 mod solver;
 mod pdf_generator;
+mod validation;
+mod constraints;
+mod exact;
+mod genetic;
+mod local_search;
+mod portfolio;
+mod bounds;
+mod stats;
+mod jobs;
+mod construction;
+mod verify;
+mod editing;
+mod explain;
+#[cfg(test)]
+mod testing;
 
-use solver::{solve_from_bytes, SolveResult};
+use solver::{solve_from_bytes, verify_from_bytes, SolveOptions, SolveResult};
+use verify::Violation;
+use editing::Suggestion;
+use jobs::{JobStatus, SolveJobs};
+use std::collections::HashMap;
 use pdf_generator::generate_pdf;
 
+/// Starts solving in the background and returns the job ID at once. Progress events
+/// carry the job ID, and `solve_finished` brings the job's final status.
 #[tauri::command]
-fn solve_rooms(
+fn start_solve(
     file_data: Vec<u8>, 
     room_size: usize, 
     iterations: usize,
+    options: SolveOptions,
+    initial_rooms: Option<HashMap<String, Vec<Vec<String>>>>,
     app: tauri::AppHandle,
+    jobs: tauri::State<'_, SolveJobs>,
+) -> u64 {
+    let job = jobs.start();
+    let job_id = job.id;
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = solve_from_bytes(file_data, room_size, iterations, &options, initial_rooms.as_ref(), &app, &job);
+        if let Err(e) = &outcome {
+            println!("Solve {} failed: {}", job.id, e);
+        }
+        job.finish(outcome);
+        let _ = app.emit_to("main", "solve_finished", job.status());
+    });
+    
+    job_id
+}
+
+#[tauri::command]
+fn get_solve_status(job_id: u64, jobs: tauri::State<'_, SolveJobs>) -> Result<JobStatus, String> {
+    Ok(jobs.get(job_id)?.status())
+}
+
+#[tauri::command]
+fn cancel_solve(job_id: u64, jobs: tauri::State<'_, SolveJobs>) -> Result<(), String> {
+    jobs.cancel(job_id)
+}
+
+/// Checks rooms produced elsewhere against the spreadsheet, room size and options,
+/// without solving anything. The app itself doesn't call this, since every result it
+/// shows carries its own `violations`; it is for scripts and other front ends that
+/// bring their own rooms.
+#[tauri::command]
+fn verify_rooms(
+    file_data: Vec<u8>,
+    room_size: usize,
+    options: SolveOptions,
+    rooms: HashMap<String, Vec<Vec<String>>>,
+) -> Result<Vec<Violation>, String> {
+    verify_from_bytes(file_data, room_size, &options, &rooms)
+}
+
+/// Moves someone to another room of a result (`room` one past the last opens a new one)
+/// and returns it rescored
+#[tauri::command]
+fn move_person(
+    result: SolveResult,
+    person_id: String,
+    category: String,
+    room: usize,
+    room_size: usize,
+    options: SolveOptions,
+) -> Result<SolveResult, String> {
+    let constraints = editing::constraints_for(&result, room_size, &options)?;
+    editing::move_person(result, &person_id, &category, room, &constraints)
+}
+
+/// Swaps two people's rooms and returns the result rescored
+#[tauri::command]
+fn swap_people(
+    result: SolveResult,
+    first_id: String,
+    second_id: String,
+    room_size: usize,
+    options: SolveOptions,
 ) -> Result<SolveResult, String> {
-    solve_from_bytes(file_data, room_size, iterations, &app)
+    let constraints = editing::constraints_for(&result, room_size, &options)?;
+    editing::swap_people(result, &first_id, &second_id, &constraints)
+}
+
+/// Moves and swaps that would give someone one of their choices, least damaging first
+#[tauri::command]
+fn suggest_fixes(
+    result: SolveResult,
+    person_id: String,
+    room_size: usize,
+    options: SolveOptions,
+) -> Result<Vec<Suggestion>, String> {
+    let constraints = editing::constraints_for(&result, room_size, &options)?;
+    editing::suggest_fixes(&result, &person_id, &constraints)
 }
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri_plugin_dialog::DialogExt;
 use std::path::PathBuf;
 
@@ -24,6 +124,7 @@ use std::path::PathBuf;
 async fn generate_pdf_report(
     result_json: String,
     event_name: String,
+    attribute_columns: Vec<String>,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
     let result: SolveResult = serde_json::from_str(&result_json)
@@ -51,7 +152,7 @@ match file_path {
         // Convert to string and then to Path
         let path_str = path.to_string();
         let path_ref = std::path::Path::new(&path_str);
-        generate_pdf(&result, &event_name, &result.people, path_ref)
+        generate_pdf(&result, &event_name, &result.people, &attribute_columns, path_ref)
     }
     None => Err("Save cancelled".to_string())
 }
@@ -61,7 +162,9 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![solve_rooms, generate_pdf_report])
+        .manage(SolveJobs::default())
+        .invoke_handler(tauri::generate_handler![start_solve, get_solve_status, cancel_solve, verify_rooms,
+                                                 move_person, swap_people, suggest_fixes, generate_pdf_report])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file
@@ -4,6 +4,7 @@ use std::io::BufWriter;
 use std::path::Path;
 use chrono::Local;
 use crate::solver::{SolveResult, Person};
+use crate::stats::ConvergencePoint;
 
 const PAGE_WIDTH: f32 = 210.0;
 const PAGE_HEIGHT: f32 = 297.0;
@@ -11,11 +12,103 @@ const MARGIN_LEFT: f32 = 20.0;
 const MARGIN_RIGHT: f32 = 190.0;
 const LINE_HEIGHT: f32 = 5.0;
 const BOTTOM_MARGIN: f32 = 20.0;
+const CHART_HEIGHT: f32 = 40.0;
+
+/// Looks up a person's display name from their ID, falling back to the ID itself
+fn display_name<'a>(people: &'a [Person], id: &'a str) -> &'a str {
+    people.iter()
+        .find(|p| p.id == id)
+        .map(|p| p.name.as_str())
+        .unwrap_or(id)
+}
+
+/// Formats the chosen attribute columns for one person, e.g. "Grade: 9, School: Lincoln"
+fn attribute_summary(people: &[Person], id: &str, attribute_columns: &[String]) -> String {
+    let Some(person) = people.iter().find(|p| p.id == id) else {
+        return String::new();
+    };
+    
+    attribute_columns.iter()
+        .filter_map(|key| person.attributes.get(key).map(|value| format!("{}: {}", key, value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn point(x: f32, y: f32) -> (Point, bool) {
+    (Point::new(Mm(x), Mm(y)), false)
+}
+
+/// Draws one pool's best over time as step lines, with its axes, below `top`: choice
+/// score in blue, on a y axis from 0 to the upper bound (or the best score, if higher),
+/// and people without any of their choices in orange, from 0 to the most there were.
+/// Getting everyone a choice comes first, so the blue line can dip as the orange drops.
+fn draw_convergence(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    points: &[ConvergencePoint],
+    search_secs: f64,
+    upper_bound: i32,
+    top: f32,
+) {
+    let left = MARGIN_LEFT + 10.0;
+    let width = MARGIN_RIGHT - left;
+    let bottom = top - CHART_HEIGHT;
+    
+    let max_score = points.iter().map(|p| p.choice_score).max().unwrap_or(0).max(upper_bound).max(1) as f32;
+    let max_without = points.iter().map(|p| p.without_choices).max().unwrap_or(0);
+    let max_secs = search_secs.max(0.001) as f32;
+    let x_at = |secs: f64| left + (secs as f32 / max_secs).min(1.0) * width;
+    let y_at = |score: i32| bottom + (score.max(0) as f32 / max_score) * CHART_HEIGHT;
+    
+    layer.set_outline_color(Color::Rgb(Rgb::new(0.6, 0.6, 0.6, None)));
+    layer.set_outline_thickness(0.5);
+    layer.add_line(Line {
+        points: vec![point(left, top), point(left, bottom), point(MARGIN_RIGHT, bottom)],
+        is_closed: false,
+    });
+    if upper_bound > 0 {
+        layer.add_line(Line {
+            points: vec![point(left, y_at(upper_bound)), point(MARGIN_RIGHT, y_at(upper_bound))],
+            is_closed: false,
+        });
+    }
+    
+    // Each best holds until the next improvement, and the last until the search ended
+    let steps = |y: &dyn Fn(&ConvergencePoint) -> f32| {
+        let mut line = Vec::new();
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                line.push(point(x_at(p.elapsed_secs), y(&points[i - 1])));
+            }
+            line.push(point(x_at(p.elapsed_secs), y(p)));
+        }
+        if let Some(last) = points.last() {
+            line.push(point(x_at(search_secs), y(last)));
+        }
+        line
+    };
+    layer.set_outline_thickness(1.0);
+    if max_without > 0 {
+        let y_without = |p: &ConvergencePoint| bottom + p.without_choices as f32 / max_without as f32 * CHART_HEIGHT;
+        layer.set_outline_color(Color::Rgb(Rgb::new(0.93, 0.55, 0.0, None)));
+        layer.add_line(Line { points: steps(&y_without), is_closed: false });
+        layer.use_text(format!("Orange: without a choice, up to {}", max_without), 7.0,
+                       Mm(MARGIN_RIGHT - 45.0), Mm(top - 2.0), font);
+    }
+    layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.44, 0.89, None)));
+    layer.add_line(Line { points: steps(&|p| y_at(p.choice_score)), is_closed: false });
+    layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    
+    layer.use_text(format!("{}", max_score as i32), 7.0, Mm(MARGIN_LEFT), Mm(top - 2.0), font);
+    layer.use_text("0", 7.0, Mm(MARGIN_LEFT + 5.0), Mm(bottom), font);
+    layer.use_text(format!("{:.1}s", search_secs), 7.0, Mm(MARGIN_RIGHT - 8.0), Mm(bottom - 4.0), font);
+}
 
 pub fn generate_pdf(
     result: &SolveResult,
     event_name: &str,
     people: &[Person],
+    attribute_columns: &[String],
     output_path: &Path,
 ) -> Result<String, String> {
     let (doc, page1, layer1) = PdfDocument::new(
@@ -52,12 +145,18 @@ pub fn generate_pdf(
     current_layer.use_text("Summary", 14.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
     y -= LINE_HEIGHT * 1.5;
     
-    let summary_items = vec![
+    let mut summary_items = vec![
         format!("Total Rooms: {}", result.total_rooms),
         format!("Choice Satisfaction Score: {}", result.choice_score),
+        format!("Upper Bound: {} (gap {:.1}%)", result.upper_bound, result.optimality_gap),
         format!("Room Balance (Imbalance): {}", result.imbalance),
         format!("People without choices: {}", result.without_choices),
     ];
+    if result.proven_optimal {
+        summary_items.push("Proven optimal: no better assignment exists".to_string());
+    } else if !result.optimal_categories.is_empty() {
+        summary_items.push(format!("Proven optimal for: {}", result.optimal_categories.join(", ")));
+    }
     
     for item in summary_items {
         current_layer.use_text(&item, 10.0, Mm(MARGIN_LEFT + 5.0), Mm(y), &font);
@@ -65,8 +164,32 @@ pub fn generate_pdf(
     }
     y -= LINE_HEIGHT;
     
+    // One line per category, since each is solved on its own
+    if result.category_stats.len() > 1 {
+        let mut categories: Vec<_> = result.category_stats.iter().collect();
+        categories.sort_by(|a, b| a.0.cmp(b.0));
+        
+        current_layer.use_text("By Category", 12.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
+        y -= LINE_HEIGHT * 1.5;
+        
+        for (category, stats) in categories {
+            if y < BOTTOM_MARGIN + 10.0 {
+                let (page, layer) = add_new_page(&doc, &mut y);
+                current_page = page;
+                current_layer = doc.get_page(current_page).get_layer(layer);
+            }
+            
+            let text = format!("{}: {} people in {} rooms, score {}, imbalance {}, {} without choices, {} mutual pairs",
+                               category, stats.people, stats.rooms, stats.choice_score, stats.imbalance,
+                               stats.without_choices, stats.mutual_pairs);
+            current_layer.use_text(&text, 9.0, Mm(MARGIN_LEFT + 5.0), Mm(y), &font);
+            y -= LINE_HEIGHT * 0.9;
+        }
+        y -= LINE_HEIGHT;
+    }
+    
     // Warnings section
-    if result.without_choices > 0 {
+    if result.without_choices > 0 || !result.soft_avoid_violations.is_empty() {
         if y < BOTTOM_MARGIN + 30.0 {
             let (page, layer) = add_new_page(&doc, &mut y);
             current_page = page;
@@ -76,28 +199,49 @@ pub fn generate_pdf(
         current_layer.use_text("Warnings", 12.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
         y -= LINE_HEIGHT * 1.5;
         
-        for (_category, rooms) in &result.rooms_by_category {
-            for room in rooms {
-                for person_name in room {
-                    if let Some(person) = people.iter().find(|p| &p.name == person_name) {
-                        if !person.choices.is_empty() {
-                            let has_choice = person.choices.iter().any(|c| room.contains(c));
-                            if !has_choice {
-                                if y < BOTTOM_MARGIN + 10.0 {
-                                    let (page, layer) = add_new_page(&doc, &mut y);
-                                    current_page = page;
-                                    current_layer = doc.get_page(current_page).get_layer(layer);
-                                }
-                                
-                                let text = format!("• {} has none of their choices in their room", person.name);
-                                current_layer.use_text(&text, 9.0, Mm(MARGIN_LEFT + 5.0), Mm(y), &font);
-                                y -= LINE_HEIGHT * 0.9;
-                            }
-                        }
-                    }
+        let mut unhappy: Vec<(&str, &str)> = result.person_stats.iter()
+            .filter(|(_, stats)| stats.is_unhappy())
+            .map(|(id, _)| (display_name(people, id), id.as_str()))
+            .collect();
+        unhappy.sort();
+        
+        for (name, id) in unhappy {
+            // The bare line for results saved before explanations existed
+            let reasons: Vec<String> = result.explanations.get(id)
+                .map(|obstacles| obstacles.iter().map(|o| o.describe(people)).collect())
+                .unwrap_or_default();
+            let mut lines = vec![if reasons.is_empty() {
+                format!("• {} has none of their choices in their room", name)
+            } else {
+                format!("• {} has none of their choices:", name)
+            }];
+            lines.extend(reasons.iter().map(|reason| format!("    – {}", reason)));
+            
+            for text in lines {
+                if y < BOTTOM_MARGIN + 10.0 {
+                    let (page, layer) = add_new_page(&doc, &mut y);
+                    current_page = page;
+                    current_layer = doc.get_page(current_page).get_layer(layer);
                 }
+                
+                current_layer.use_text(&text, 9.0, Mm(MARGIN_LEFT + 5.0), Mm(y), &font);
+                y -= LINE_HEIGHT * 0.9;
             }
         }
+        
+        for violation in &result.soft_avoid_violations {
+            if y < BOTTOM_MARGIN + 10.0 {
+                let (page, layer) = add_new_page(&doc, &mut y);
+                current_page = page;
+                current_layer = doc.get_page(current_page).get_layer(layer);
+            }
+            
+            let text = format!("• {} shares a {} room with {}, whom they preferred not to",
+                               display_name(people, &violation.person), violation.category,
+                               display_name(people, &violation.avoided));
+            current_layer.use_text(&text, 9.0, Mm(MARGIN_LEFT + 5.0), Mm(y), &font);
+            y -= LINE_HEIGHT * 0.9;
+        }
         y -= LINE_HEIGHT;
     }
     
@@ -133,7 +277,12 @@ for (category, rooms) in &result.rooms_by_category {
     let mut right_column_y = y;
     
     for room in rooms {
-        let room_height = (room.len() as f32 * LINE_HEIGHT * 0.7) + LINE_HEIGHT * 1.5;
+        let attribute_lines = room.iter()
+            .filter(|id| !attribute_summary(people, id, attribute_columns).is_empty())
+            .count();
+        let room_height = (room.len() as f32 * LINE_HEIGHT * 0.7)
+            + (attribute_lines as f32 * LINE_HEIGHT * 0.6)
+            + LINE_HEIGHT * 1.5;
         
         // Determine which column to use
         let (x_pos, column_y) = if column == 0 {
@@ -180,14 +329,27 @@ for (category, rooms) in &result.rooms_by_category {
         *column_y -= LINE_HEIGHT * 0.9;
         
         // Room members
-        for member in room {
-            let display_name = if member.len() > 22 {
-                format!("• {}...", &member[..19])
+        for member_id in room {
+            let member = display_name(people, member_id);
+            let line = if member.chars().count() > 22 {
+                format!("• {}...", member.chars().take(19).collect::<String>())
             } else {
                 format!("• {}", member)
             };
-            current_layer.use_text(&display_name, 8.0, Mm(x_pos + 2.0), Mm(*column_y), &font);
+            current_layer.use_text(&line, 8.0, Mm(x_pos + 2.0), Mm(*column_y), &font);
             *column_y -= LINE_HEIGHT * 0.7;
+            
+            // Selected attributes on a smaller line underneath
+            let attributes = attribute_summary(people, member_id, attribute_columns);
+            if !attributes.is_empty() {
+                let attributes = if attributes.chars().count() > 40 {
+                    format!("{}...", attributes.chars().take(37).collect::<String>())
+                } else {
+                    attributes
+                };
+                current_layer.use_text(&attributes, 6.5, Mm(x_pos + 5.0), Mm(*column_y), &font);
+                *column_y -= LINE_HEIGHT * 0.6;
+            }
         }
         
         room_num += 1;
@@ -223,26 +385,30 @@ for (category, rooms) in &result.rooms_by_category {
             current_layer.use_text(&format!("Room {}:", room_num), 10.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
             y -= LINE_HEIGHT;
             
-            for person_name in room {
-                if let Some(person) = people.iter().find(|p| &p.name == person_name) {
+            for person_id in room {
+                if let Some(person) = people.iter().find(|p| &p.id == person_id) {
                     let mut parts = Vec::new();
                     
                     // Who they chose in this room
                     let chose: Vec<&str> = person.choices.iter()
                         .filter(|c| room.contains(c))
-                        .map(|s| s.as_str())
+                        .map(|c| display_name(people, c))
                         .collect();
                     if !chose.is_empty() {
-                        parts.push(format!("chose {}", chose.join(", ")));
+                        let rank = result.person_stats.get(person_id)
+                            .and_then(|stats| stats.best_choice_rank)
+                            .map(|rank| format!(" (best: choice {})", rank))
+                            .unwrap_or_default();
+                        parts.push(format!("chose {}{}", chose.join(", "), rank));
                     }
                     
                     // Who chose them
                     let mut chosen_by = Vec::new();
-                    for other_name in room {
-                        if other_name != person_name {
-                            if let Some(other) = people.iter().find(|p| &p.name == other_name) {
-                                if other.choices.contains(person_name) {
-                                    chosen_by.push(other_name.as_str());
+                    for other_id in room {
+                        if other_id != person_id {
+                            if let Some(other) = people.iter().find(|p| &p.id == other_id) {
+                                if other.choices.contains(person_id) {
+                                    chosen_by.push(other.name.as_str());
                                 }
                             }
                         }
@@ -253,7 +419,19 @@ for (category, rooms) in &result.rooms_by_category {
                     
                     // Avoids
                     if !person.avoids.is_empty() {
-                        parts.push(format!("avoids {}", person.avoids.join(", ")));
+                        let avoids: Vec<&str> = person.avoids.iter()
+                            .map(|a| display_name(people, a))
+                            .collect();
+                        parts.push(format!("avoids {}", avoids.join(", ")));
+                    }
+                    
+                    // Soft avoids that ended up in this room
+                    let prefer_not: Vec<&str> = person.prefer_not.iter()
+                        .filter(|other| room.contains(other))
+                        .map(|other| display_name(people, other))
+                        .collect();
+                    if !prefer_not.is_empty() {
+                        parts.push(format!("preferred not {}", prefer_not.join(", ")));
                     }
                     
                     let text = if parts.is_empty() {
@@ -285,6 +463,46 @@ for (category, rooms) in &result.rooms_by_category {
         }
     }
     
+    // Appendix: how each searched pool's best score improved over time
+    if !result.convergence.is_empty() {
+        let (page, layer) = add_new_page(&doc, &mut y);
+        current_page = page;
+        current_layer = doc.get_page(current_page).get_layer(layer);
+        
+        current_layer.use_text("Appendix: Search Convergence", 14.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
+        y -= LINE_HEIGHT * 2.0;
+        
+        let mut pools: Vec<_> = result.convergence.iter().filter(|(_, points)| !points.is_empty()).collect();
+        pools.sort_by(|a, b| a.0.cmp(b.0));
+        
+        for (category, points) in pools {
+            if y < BOTTOM_MARGIN + CHART_HEIGHT + LINE_HEIGHT * 4.0 {
+                let (page, layer) = add_new_page(&doc, &mut y);
+                current_page = page;
+                current_layer = doc.get_page(current_page).get_layer(layer);
+            }
+            
+            let last = &points[points.len() - 1];
+            let upper_bound = result.category_bounds.get(category).map_or(0, |b| b.upper_bound);
+            let verdict = if last.elapsed_secs < result.search_secs / 2.0 {
+                "settled early, so more iterations are unlikely to help"
+            } else {
+                "still improving late, so more iterations may help"
+            };
+            
+            current_layer.use_text(format!("{}: score {} of bound {}", category, last.choice_score, upper_bound),
+                                   10.0, Mm(MARGIN_LEFT), Mm(y), &font_bold);
+            y -= LINE_HEIGHT;
+            current_layer.use_text(format!("{} improvements, the last after {:.1}s of {:.1}s; {}",
+                                            points.len(), last.elapsed_secs, result.search_secs, verdict),
+                                   8.0, Mm(MARGIN_LEFT), Mm(y), &font);
+            y -= LINE_HEIGHT;
+            
+            draw_convergence(&current_layer, &font, points, result.search_secs, upper_bound, y);
+            y -= CHART_HEIGHT + LINE_HEIGHT * 2.0;
+        }
+    }
+    
     // Save
     doc.save(&mut BufWriter::new(File::create(output_path).map_err(|e| e.to_string())?))
         .map_err(|e| e.to_string())?;
@@ -0,0 +1,135 @@
+use crate::constraints::Constraints;
+use crate::genetic;
+use crate::local_search;
+use crate::solver::{self, Person, PoolOutcome, RoomDistribution, SearchProgress, StopReason, Strategy};
+
+pub const DEFAULT_TIME_BUDGET_SECS: u64 = 30;
+
+/// Random-search iterations per call, each starting where the last stopped
+const RANDOM_SLICE: usize = 25_000;
+
+/// Races every strategy on one pool until the time budget in `progress` runs out, or
+/// every member has stopped early on its own.
+///
+/// The random search, genetic search and local search share the rayon pool and each
+/// offers its improvements to `progress`, which keeps the best from any of them. Local
+/// search restarts from that shared best, so a good find by one strategy gets polished
+/// by another. Returns the overall best and the strategy that produced it.
+pub fn race(
+    pool: &str,
+    people: &[Person],
+    distribution: &RoomDistribution,
+    constraints: &Constraints,
+    population_size: usize,
+    progress: &SearchProgress,
+) -> PoolOutcome {
+    let random = || {
+        let mut best = progress.best(pool).map(|(solution, eval, _)| (solution, eval));
+        let mut done = 0;
+        let mut next = 0;
+        let mut since_improvement = 0;
+        loop {
+            let before = best.as_ref().map(|(_, eval)| eval.score);
+            let outcome = solver::random_search(pool, people, distribution, constraints,
+                                                next..next + RANDOM_SLICE, best, progress);
+            best = outcome.best;
+            done += outcome.iterations;
+            next += RANDOM_SLICE;
+
+            // Each slice starts its own stall count, so keep one across slices here
+            if best.as_ref().map(|(_, eval)| eval.score) != before {
+                since_improvement = 0;
+            } else {
+                since_improvement += outcome.iterations;
+            }
+            let shared = progress.best_eval(pool);
+            if let Some(reason) = progress.stop_reason(shared.as_ref(), since_improvement) {
+                return (done, reason);
+            }
+            if outcome.stop_reason != StopReason::Finished {
+                return (done, outcome.stop_reason);
+            }
+        }
+    };
+
+    let local = || {
+        let round = rayon::current_num_threads() * local_search::MOVES_PER_RESTART;
+        let mut done = 0;
+        let mut next_seed = 0;
+        loop {
+            let outcome = local_search::search(pool, people, distribution, constraints, round, next_seed, progress);
+            done += outcome.iterations;
+            next_seed += round / local_search::MOVES_PER_RESTART;
+            if outcome.stop_reason != StopReason::Finished {
+                return (done, outcome.stop_reason);
+            }
+        }
+    };
+
+    let genetic = || {
+        let outcome = genetic::evolve(pool, people, distribution, constraints, population_size, usize::MAX, progress);
+        (outcome.iterations, outcome.stop_reason)
+    };
+
+    let ((random_done, random_reason), ((genetic_done, genetic_reason), (local_done, local_reason))) =
+        rayon::join(random, || rayon::join(genetic, local));
+
+    println!("{}: portfolio ran {} random iterations, {} genetic children, {} local moves",
+             pool, random_done, genetic_done, local_done);
+
+    let best = progress.best(pool);
+    let strategy = best.as_ref().map_or(Strategy::Portfolio, |(_, _, strategy)| *strategy);
+    if best.is_some() {
+        println!("{}: best found by {:?}", pool, strategy);
+    }
+
+    PoolOutcome {
+        best: best.map(|(solution, eval, _)| (solution, eval)),
+        iterations: random_done + genetic_done + local_done,
+        strategy,
+        stop_reason: overall_stop_reason([random_reason, genetic_reason, local_reason]),
+    }
+}
+
+/// A cancel wins, then the target if any member reached it; the race only counts as
+/// stalled when every member stalled
+fn overall_stop_reason(reasons: [StopReason; 3]) -> StopReason {
+    if reasons.contains(&StopReason::Cancelled) {
+        StopReason::Cancelled
+    } else if reasons.contains(&StopReason::TargetReached) {
+        StopReason::TargetReached
+    } else if reasons.iter().all(|reason| *reason == StopReason::Stalled) {
+        StopReason::Stalled
+    } else {
+        StopReason::TimeLimit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construction::SolutionBuilder;
+    use crate::jobs::SolveJobs;
+    use crate::solver::SolveOptions;
+    use crate::testing::{assert_everyone_placed_once, constraints, ring_of_boys};
+
+    #[test]
+    fn race_places_everyone_once_and_never_loses_the_seed() {
+        let people = ring_of_boys(12);
+        let constraints = constraints(&people, "", 4);
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let seed = SolutionBuilder::new("Boys", &people, &distribution, &constraints).build(1).unwrap();
+        let seed_eval = solver::evaluate_solution(&seed, &people, &constraints);
+        let job = SolveJobs::default().start();
+        let options = SolveOptions { time_limit_secs: Some(1), ..Default::default() };
+        let progress = SearchProgress::unreported(&job, usize::MAX, &options);
+        progress.improved("Boys", Strategy::RandomSearch, 0, &seed, &seed_eval);
+
+        let outcome = race("Boys", &people, &distribution, &constraints, 20, &progress);
+        let (solution, eval) = outcome.best.unwrap();
+
+        assert_everyone_placed_once(&solution, &people);
+        assert!(eval.score >= seed_eval.score, "{} < {}", eval.score, seed_eval.score);
+        assert_eq!(outcome.stop_reason, StopReason::TimeLimit);
+    }
+}
@@ -1,25 +1,49 @@
-use calamine::{Reader, Xlsx, open_workbook, Data};
-use std::collections::{HashMap, HashSet};
-use rand::Rng;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
+use calamine::{Reader, Xlsx, Data, DataType};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 use tauri::{Emitter, Manager};
 use serde_json::json;
+use crate::validation::{self, ValidationIssue};
+use crate::exact;
+use crate::genetic;
+use crate::local_search;
+use crate::portfolio;
+use crate::construction::{Pairing, SolutionBuilder};
+use crate::jobs::SolveJob;
+use crate::verify::{self, Violation};
+use crate::explain::{self, ChoiceObstacle};
+use crate::bounds::{self, CategoryBound};
+use crate::stats::{self, CategoryStats, ConvergencePoint, PersonStats};
+use crate::constraints::{self, Constraints, LeaderPolicy, RuleViolation, SoftAvoidViolation};
 
 // Add Serialize to your structs
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Person {
+    /// Identity key used by choices, avoids and rooms; the ID column, or the name without one
+    pub id: String,
+    /// Display name for the UI and PDF
     pub name: String,
     pub category: String,
     pub choices: Vec<String>,
+    /// Hard avoids: never placed in the same room
     pub avoids: Vec<String>,
+    /// Soft avoids: allowed in the same room, at a cost in the objective
+    #[serde(default)]
+    pub prefer_not: Vec<String>,
+    /// Any extra spreadsheet columns (Grade, School, Notes, ...), keyed by header
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Room {
     pub category: String,
+    /// Person IDs
     pub members: Vec<String>,
     pub max_size: usize,
 }
@@ -27,7 +51,7 @@ pub struct Room {
 // ... rest of your code (all the functions) ...
 
 impl Room {
-    fn new(category: String, max_size: usize) -> Room {
+    pub fn new(category: String, max_size: usize) -> Room {
         Room {
             category,
             members: Vec::new(),
@@ -35,28 +59,29 @@ impl Room {
         }
     }
     
-    fn add_person(&mut self, name: String) {
-        self.members.push(name);
+    pub fn add_person(&mut self, id: String) {
+        self.members.push(id);
     }
     
-    fn is_full(&self) -> bool {
-        self.members.len() >= self.max_size
-    }
-    
-    fn has_space(&self) -> bool {
+    pub fn has_space(&self) -> bool {
         self.members.len() < self.max_size
     }
 }
 
-type Solution = Vec<Room>;
+pub type Solution = Vec<Room>;
 
 #[derive(Debug, Clone)]
-struct RoomDistribution {
-    sizes: Vec<usize>,
+pub struct RoomDistribution {
+    pub sizes: Vec<usize>,
 }
 
 impl RoomDistribution {
     fn new(total_people: usize, max_size: usize) -> RoomDistribution {
+        RoomDistribution::with_min_rooms(total_people, max_size, 0)
+    }
+    
+    /// Like `new`, but never fewer than `min_rooms` rooms (e.g. one per leader)
+    fn with_min_rooms(total_people: usize, max_size: usize, min_rooms: usize) -> RoomDistribution {
         let mut sizes = Vec::new();
         
         if total_people == 0 {
@@ -64,7 +89,8 @@ impl RoomDistribution {
         }
         
         // Calculate number of rooms needed
-        let num_rooms = (total_people + max_size - 1) / max_size;
+        let num_rooms = ((total_people + max_size - 1) / max_size)
+            .max(min_rooms.min(total_people));
         
         // Distribute as evenly as possible
         let base_size = total_people / num_rooms;
@@ -94,163 +120,13 @@ impl RoomDistribution {
 
 
 
-fn generate_random_solution(
-    people: &[Person],
-    target_distributions: &HashMap<String, RoomDistribution>,
-    seed: usize,
-) -> Option<Solution> {
-    let mut rng = rand::rngs::StdRng::seed_from_u64(seed as u64);
-    let mut solution = Vec::new();
-    
-    // Create rooms based on target distributions
-    for (category, dist) in target_distributions {
-        for &size in &dist.sizes {
-            solution.push(Room::new(category.clone(), size));
-        }
-    }
-    
-    let mut placed: std::collections::HashSet<String> = std::collections::HashSet::new();
-    
-    // Phase 1: Greedily place people with mutual choices together
-let mut people_list: Vec<&Person> = people.iter().collect();
-
-// Sort by "social connectedness" - people with more mutual friends first
-people_list.sort_by_key(|p| {
-    let mutual_count = p.choices.iter()
-        .filter(|choice_name| {
-            people.iter().any(|other| 
-                &other.name == *choice_name && 
-                other.category == p.category &&
-                other.choices.contains(&p.name)
-            )
-        })
-        .count();
-    -(mutual_count as i32)
-});
-
-// Add some randomness to avoid always getting the same solution
-if seed % 3 > 0 {
-    people_list.shuffle(&mut rng);
-}
-
-for person in &people_list {
-    if placed.contains(&person.name) {
-        continue;
-    }
-    
-    // Find mutual friends who aren't placed yet
-    let mut mutual_friends: Vec<&Person> = Vec::new();
-    for choice_name in &person.choices {
-        if let Some(friend) = people.iter().find(|p| &p.name == choice_name) {
-            if friend.category == person.category 
-                && !placed.contains(&friend.name)
-                && friend.choices.contains(&person.name) {
-                mutual_friends.push(friend);
-            }
-        }
-    }
-    
-    // Try to place person with at least SOME mutual friends, not necessarily all
-    if !mutual_friends.is_empty() {
-        // Sort mutual friends by their connectedness (place more connected people first)
-        mutual_friends.sort_by_key(|f| {
-            let count = person.choices.iter()
-                .filter(|c| f.choices.contains(c))
-                .count();
-            -(count as i32)
-        });
-        
-        // Try to fit as many mutual friends as possible
-        let mut candidate_rooms: Vec<usize> = solution.iter()
-            .enumerate()
-            .filter(|(_, r)| r.category == person.category && r.has_space())
-            .map(|(i, _)| i)
-            .collect();
-        
-        candidate_rooms.shuffle(&mut rng);
-        
-        for room_idx in candidate_rooms {
-            let mut group_to_place = vec![person];
-            
-            // Try adding mutual friends one by one
-            for friend in &mutual_friends {
-                if solution[room_idx].members.len() + group_to_place.len() < solution[room_idx].max_size {
-                    group_to_place.push(friend);
-                }
-            }
-            
-            // Check if all in group can be added
-            let mut all_valid = true;
-            for member in &group_to_place {
-                if !can_add_person_to_room(member, &solution[room_idx], people) {
-                    all_valid = false;
-                    break;
-                }
-            }
-            
-            if all_valid {
-                // Place the group
-                for member in &group_to_place {
-                    solution[room_idx].add_person(member.name.clone());
-                    placed.insert(member.name.clone());
-                }
-                break;
-            }
-        }
-    }
-}
-    
-    // Phase 2: Place remaining people, preferring rooms with their choices
-    let mut remaining: Vec<&Person> = people.iter()
-        .filter(|p| !placed.contains(&p.name))
-        .collect();
-    remaining.shuffle(&mut rng);
-    
-    for person in remaining {
-        let mut best_rooms: Vec<(usize, usize)> = Vec::new();
-        
-        for (idx, room) in solution.iter().enumerate() {
-            if room.category != person.category || !room.has_space() {
-                continue;
-            }
-            
-            if !can_add_person_to_room(person, room, people) {
-                continue;
-            }
-            
-            // Count how many of their choices are in this room
-            let choice_count = person.choices.iter()
-                .filter(|choice| room.members.contains(choice))
-                .count();
-            
-            best_rooms.push((idx, choice_count));
-        }
-        
-        if best_rooms.is_empty() {
-            // Can't place this person
-            return None;
-        }
-        
-        // Sort by choice count (prefer rooms with more choices)
-        best_rooms.sort_by_key(|(_, count)| -(*count as i32));
-        
-        // If there's a tie at the top, randomize among them
-        let max_count = best_rooms[0].1;
-        let top_rooms: Vec<usize> = best_rooms.iter()
-            .filter(|(_, count)| *count == max_count)
-            .map(|(idx, _)| *idx)
-            .collect();
-        
-        let room_idx = top_rooms[rng.gen_range(0..top_rooms.len())];
-        solution[room_idx].add_person(person.name.clone());
-        placed.insert(person.name.clone());
-    }
-    
-    Some(solution)
-}
-
-fn can_add_person_to_room(person: &Person, room: &Room, all_people: &[Person]) -> bool {
-    if person.category != room.category {
+pub fn can_add_person_to_room(
+    person: &Person,
+    room: &Room,
+    all_people: &[Person],
+    constraints: &Constraints,
+) -> bool {
+    if constraints.pool_for(person) != room.category {
         return false;
     }
     
@@ -259,14 +135,14 @@ fn can_add_person_to_room(person: &Person, room: &Room, all_people: &[Person]) -
             return false;
         }
         
-        if let Some(member_data) = all_people.iter().find(|p| &p.name == room_member) {
-            if member_data.avoids.contains(&person.name) {
+        if let Some(member_data) = all_people.iter().find(|p| &p.id == room_member) {
+            if member_data.avoids.contains(&person.id) {
                 return false;
             }
         }
     }
     
-    true
+    constraints.allows(person, room, all_people)
 }
 
 fn count_people_without_choices(solution: &Solution, people: &[Person]) -> usize {
@@ -274,7 +150,7 @@ fn count_people_without_choices(solution: &Solution, people: &[Person]) -> usize
     
     for room in solution {
         for person_name in &room.members {
-            if let Some(person) = people.iter().find(|p| p.name == *person_name) {
+            if let Some(person) = people.iter().find(|p| p.id == *person_name) {
                 if !person.choices.is_empty() {
                     let has_choice = person.choices.iter()
                         .any(|choice| room.members.contains(choice));
@@ -290,53 +166,365 @@ fn count_people_without_choices(solution: &Solution, people: &[Person]) -> usize
     count
 }
 
-fn read_spreadsheet(filename: &str) -> Result<Vec<Person>, Box<dyn std::error::Error>> {
-    let mut workbook: Xlsx<_> = open_workbook(filename)?;
+fn read_spreadsheet(
+    file_bytes: &[u8],
+    duplicate_policy: DuplicatePolicy,
+) -> Result<(Vec<Person>, Vec<ValidationIssue>), Box<dyn std::error::Error>> {
+    let mut workbook = Xlsx::new(std::io::Cursor::new(file_bytes))?;
     
+    let sheet = workbook.sheet_names().first().cloned().unwrap_or_default();
     let range = workbook
         .worksheet_range_at(0)
         .ok_or("No worksheet found")??;
     
+    // Spreadsheet coordinates of the first cell, so issues point at the right row/column
+    let (first_row, first_col) = range.start().unwrap_or((0, 0));
+    let first_row = first_row as usize;
+    let first_col = first_col as usize;
+    
     let mut rows = range.rows();
     
     let headers = rows.next().ok_or("Empty spreadsheet")?;
     
     let name_col = find_column(headers, "Name")?;
     let category_col = find_column(headers, "Category")?;
+    let id_col = find_column(headers, "ID").ok();
+    let timestamp_col = find_column(headers, "Timestamp").ok();
     
     let choice_cols = find_columns_starting_with(headers, "Choice");
     let avoid_cols = find_columns_starting_with(headers, "Avoid");
+    let prefer_not_cols = find_columns_starting_with(headers, "PreferNot");
+    
+    // Every other labelled column is carried through as an attribute
+    let attribute_cols: Vec<(usize, String)> = headers.iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            *i != name_col && *i != category_col && Some(*i) != id_col && Some(*i) != timestamp_col
+                && !choice_cols.contains(i) && !avoid_cols.contains(i) && !prefer_not_cols.contains(i)
+        })
+        .map(|(i, cell)| (i, cell.to_string().trim().to_string()))
+        .filter(|(_, header)| !header.is_empty())
+        .collect();
     
-    println!("Found columns: name={}, category={}, {} choices, {} avoids", 
-             name_col, category_col, choice_cols.len(), avoid_cols.len());
+    println!("Found columns: name={}, category={}, id={:?}, {} choices, {} avoids, {} prefer-nots, {} attributes", 
+             name_col, category_col, id_col, choice_cols.len(), avoid_cols.len(), prefer_not_cols.len(),
+             attribute_cols.len());
     
-    let mut people = Vec::new();
+    let mut raw_rows = Vec::new();
+    let mut issues = Vec::new();
     
-    for row in rows {
-        let name = get_cell_as_string(row, name_col)?;
-        let category = get_cell_as_string(row, category_col)?;
+    // First pass: read every row so choices can be matched against the full list of IDs and names
+    for (idx, row) in rows.enumerate() {
+        // Header is the first row of the range, data starts one below it
+        let row_num = first_row + idx + 2;
         
-        let choices: Vec<String> = choice_cols
-            .iter()
-            .filter_map(|&col| get_cell_as_string(row, col).ok())
-            .filter(|s| !s.is_empty())
-            .collect();
+        if row.iter().all(|cell| cell.to_string().trim().is_empty()) {
+            continue;
+        }
         
-        let avoids: Vec<String> = avoid_cols
-            .iter()
-            .filter_map(|&col| get_cell_as_string(row, col).ok())
-            .filter(|s| !s.is_empty())
+        let name = get_cell_as_string(row, name_col);
+        let category = get_cell_as_string(row, category_col);
+        
+        if name.is_empty() {
+            issues.push(ValidationIssue::error(&sheet, row_num, first_col + name_col,
+                "Blank name; fill it in or delete the row".to_string()));
+            continue;
+        }
+        
+        // Without an ID column the name doubles as the identity key
+        let id = match id_col {
+            Some(col) => {
+                let id = get_cell_as_string(row, col);
+                if id.is_empty() {
+                    issues.push(ValidationIssue::error(&sheet, row_num, first_col + col,
+                        format!("Blank ID for '{}'", name)));
+                    continue;
+                }
+                id
+            }
+            None => name.clone(),
+        };
+        
+        if category.is_empty() {
+            issues.push(ValidationIssue::error(&sheet, row_num, first_col + category_col,
+                format!("Blank category for '{}'", name)));
+        }
+        
+        let read_cells = |cols: &[usize]| -> Vec<(usize, String)> {
+            cols.iter()
+                .map(|&col| (col, get_cell_as_string(row, col)))
+                .filter(|(_, value)| !value.is_empty())
+                .collect()
+        };
+        
+        let mut timestamp = None;
+        if let Some(col) = timestamp_col {
+            let text = get_cell_as_string(row, col);
+            timestamp = row.get(col).and_then(parse_timestamp);
+            if timestamp.is_none() && !text.is_empty() {
+                issues.push(ValidationIssue::warning(&sheet, row_num, first_col + col,
+                    format!("Timestamp '{}' for '{}' isn't a date; their responses are ordered by row", text, name)));
+            }
+        }
+        
+        let attributes: BTreeMap<String, String> = attribute_cols.iter()
+            .map(|(col, header)| (header.clone(), get_cell_as_string(row, *col)))
+            .filter(|(_, value)| !value.is_empty())
             .collect();
         
-        people.push(Person {
+        raw_rows.push(RawRow {
+            row: row_num,
+            id,
             name,
             category,
-            choices,
-            avoids,
+            attributes,
+            timestamp,
+            choice_cells: read_cells(&choice_cols),
+            avoid_cells: read_cells(&avoid_cols),
+            prefer_not_cells: read_cells(&prefer_not_cols),
+        });
+    }
+    
+    let ids: HashSet<String> = raw_rows.iter().map(|r| r.id.clone()).collect();
+    let mut ids_by_name: HashMap<String, Vec<String>> = HashMap::new();
+    for raw in &raw_rows {
+        let entry = ids_by_name.entry(raw.name.clone()).or_default();
+        if !entry.contains(&raw.id) {
+            entry.push(raw.id.clone());
+        }
+    }
+    
+    // Second pass: turn choice/avoid cells into IDs and check them
+    let mut submissions = Vec::new();
+    
+    for raw in raw_rows {
+        let mut choices: Vec<String> = Vec::new();
+        for (col, text) in &raw.choice_cells {
+            let Some(choice) = resolve_reference(text, &ids, &ids_by_name) else {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' matches more than one person; use their ID instead", text)));
+                continue;
+            };
+            if choice == raw.id {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' chose themselves; choice ignored", raw.name)));
+                continue;
+            }
+            choices.push(choice);
+        }
+        
+        let mut avoids: Vec<String> = Vec::new();
+        for (col, text) in &raw.avoid_cells {
+            let Some(avoid) = resolve_reference(text, &ids, &ids_by_name) else {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' matches more than one person; use their ID instead", text)));
+                continue;
+            };
+            if avoid == raw.id {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' avoids themselves; avoid ignored", raw.name)));
+                continue;
+            }
+            if choices.contains(&avoid) {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' both chooses and avoids '{}'; the avoid wins", raw.name, text)));
+                choices.retain(|c| c != &avoid);
+            }
+            avoids.push(avoid);
+        }
+        
+        let mut prefer_not: Vec<String> = Vec::new();
+        for (col, text) in &raw.prefer_not_cells {
+            let Some(other) = resolve_reference(text, &ids, &ids_by_name) else {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' matches more than one person; use their ID instead", text)));
+                continue;
+            };
+            if other == raw.id {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' prefers not to room with themselves; ignored", raw.name)));
+                continue;
+            }
+            if avoids.contains(&other) {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' already avoids '{}'; prefer-not ignored", raw.name, text)));
+                continue;
+            }
+            if choices.contains(&other) {
+                issues.push(ValidationIssue::warning(&sheet, raw.row, first_col + col,
+                    format!("'{}' both chooses and prefers not '{}'; the choice is ignored", raw.name, text)));
+                choices.retain(|c| c != &other);
+            }
+            prefer_not.push(other);
+        }
+        
+        submissions.push(Submission {
+            person: Person {
+                id: raw.id,
+                name: raw.name,
+                category: raw.category,
+                choices,
+                avoids,
+                prefer_not,
+                attributes: raw.attributes,
+            },
+            row: raw.row,
+            timestamp: raw.timestamp,
+        });
+    }
+    
+    let people = resolve_duplicates(submissions, duplicate_policy, &sheet, first_col + id_col.unwrap_or(name_col), &mut issues);
+    
+    Ok((people, issues))
+}
+
+/// A data row as read from the sheet, before choices and avoids are matched to people
+struct RawRow {
+    row: usize,
+    id: String,
+    name: String,
+    category: String,
+    attributes: BTreeMap<String, String>,
+    timestamp: Option<f64>,
+    choice_cells: Vec<(usize, String)>,
+    avoid_cells: Vec<(usize, String)>,
+    prefer_not_cells: Vec<(usize, String)>,
+}
+
+/// Matches a Choice/Avoid cell to a person's ID. The cell may hold the ID itself or a
+/// display name; `None` means the name is shared by several people and can't be resolved.
+/// Unknown references are passed through unchanged, they just never match anyone.
+fn resolve_reference(
+    text: &str,
+    ids: &HashSet<String>,
+    ids_by_name: &HashMap<String, Vec<String>>,
+) -> Option<String> {
+    if ids.contains(text) {
+        return Some(text.to_string());
+    }
+    
+    match ids_by_name.get(text) {
+        Some(matches) if matches.len() == 1 => Some(matches[0].clone()),
+        Some(_) => None,
+        None => Some(text.to_string()),
+    }
+}
+
+/// One spreadsheet row, kept with its position so duplicates can be resolved and reported
+struct Submission {
+    person: Person,
+    row: usize,
+    timestamp: Option<f64>,
+}
+
+/// Collapses repeated form responses for the same person (same ID, or same name when
+/// there is no ID column) according to the chosen policy.
+/// People are returned in the order their first response appears.
+fn resolve_duplicates(
+    submissions: Vec<Submission>,
+    policy: DuplicatePolicy,
+    sheet: &str,
+    key_col: usize,
+    issues: &mut Vec<ValidationIssue>,
+) -> Vec<Person> {
+    let mut order: Vec<String> = Vec::new();
+    let mut by_id: HashMap<String, Vec<Submission>> = HashMap::new();
+    
+    for submission in submissions {
+        if !by_id.contains_key(&submission.person.id) {
+            order.push(submission.person.id.clone());
+        }
+        by_id.entry(submission.person.id.clone())
+            .or_default()
+            .push(submission);
+    }
+    
+    let mut people = Vec::new();
+    
+    for id in order {
+        let mut group = by_id.remove(&id).unwrap();
+        let name = group[0].person.name.clone();
+        
+        if group.len() == 1 {
+            people.push(group.remove(0).person);
+            continue;
+        }
+        
+        let rows: Vec<String> = group.iter().map(|s| s.row.to_string()).collect();
+        
+        // Oldest response first, latest last; without timestamps this is sheet order
+        group.sort_by(|a, b| {
+            a.timestamp.unwrap_or(f64::NEG_INFINITY)
+                .total_cmp(&b.timestamp.unwrap_or(f64::NEG_INFINITY))
+                .then(a.row.cmp(&b.row))
         });
+        
+        match policy {
+            DuplicatePolicy::Fail => {
+                for submission in &group {
+                    issues.push(ValidationIssue::error(sheet, submission.row, key_col,
+                        format!("Duplicate response from '{}' (rows {})", name, rows.join(", "))));
+                }
+            }
+            DuplicatePolicy::KeepFirst | DuplicatePolicy::KeepLatest => {
+                let keep_idx = if policy == DuplicatePolicy::KeepFirst {
+                    group.iter().enumerate().min_by_key(|(_, s)| s.row).map(|(i, _)| i).unwrap()
+                } else {
+                    group.len() - 1
+                };
+                
+                let kept_row = group[keep_idx].row;
+                for (i, submission) in group.iter().enumerate() {
+                    if i != keep_idx {
+                        issues.push(ValidationIssue::warning(sheet, submission.row, key_col,
+                            format!("Duplicate response from '{}' ignored; using row {}", name, kept_row)));
+                    }
+                }
+                people.push(group.swap_remove(keep_idx).person);
+            }
+            DuplicatePolicy::Merge => {
+                // The latest response decides the category and attributes, choices and avoids are combined
+                let mut merged = group.pop().unwrap();
+                for submission in group.iter().rev() {
+                    if submission.person.category != merged.person.category {
+                        issues.push(ValidationIssue::warning(sheet, submission.row, key_col,
+                            format!("Duplicate response from '{}' has category '{}'; using '{}' from row {}",
+                                    name, submission.person.category, merged.person.category, merged.row)));
+                    } else {
+                        issues.push(ValidationIssue::warning(sheet, submission.row, key_col,
+                            format!("Duplicate response from '{}' merged into row {}", name, merged.row)));
+                    }
+                    
+                    for choice in &submission.person.choices {
+                        if !merged.person.choices.contains(choice) {
+                            merged.person.choices.push(choice.clone());
+                        }
+                    }
+                    for avoid in &submission.person.avoids {
+                        if !merged.person.avoids.contains(avoid) {
+                            merged.person.avoids.push(avoid.clone());
+                        }
+                    }
+                    for other in &submission.person.prefer_not {
+                        if !merged.person.prefer_not.contains(other) {
+                            merged.person.prefer_not.push(other.clone());
+                        }
+                    }
+                    for (key, value) in &submission.person.attributes {
+                        merged.person.attributes.entry(key.clone()).or_insert(value.clone());
+                    }
+                }
+                
+                let avoids = merged.person.avoids.clone();
+                let prefer_not = merged.person.prefer_not.clone();
+                merged.person.choices.retain(|c| !avoids.contains(c) && !prefer_not.contains(c));
+                merged.person.prefer_not.retain(|c| !avoids.contains(c));
+                people.push(merged.person);
+            }
+        }
     }
     
-    Ok(people)
+    people
 }
 
 fn find_column(headers: &[Data], name: &str) -> Result<usize, String> {
@@ -358,7 +546,8 @@ fn find_columns_starting_with(headers: &[Data], prefix: &str) -> Vec<usize> {
         .enumerate()
         .filter_map(|(i, cell)| {
             if let Data::String(s) = cell {
-                let cell_str = s.trim().to_lowercase();
+                // "Prefer Not 1" and "PreferNot1" are the same column kind
+                let cell_str = s.trim().to_lowercase().replace(' ', "");
                 if cell_str.starts_with(&prefix.to_lowercase()) {
                     Some(i)
                 } else {
@@ -371,10 +560,46 @@ fn find_columns_starting_with(headers: &[Data], prefix: &str) -> Vec<usize> {
         .collect()
 }
 
-fn get_cell_as_string(row: &[Data], col: usize) -> Result<String, String> {
+/// Text timestamps as exported by Google Forms and others; month first unless that can't be
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m/%d/%Y %I:%M %p",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+];
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d/%m/%Y", "%d.%m.%Y"];
+
+/// A Timestamp cell as an Excel date serial (days since 1899-12-30), so real dates and
+/// dates typed as text compare with each other. `None` for blanks and unreadable text.
+fn parse_timestamp(cell: &Data) -> Option<f64> {
+    let text = match cell {
+        Data::DateTime(dt) => return Some(dt.as_f64()),
+        Data::String(text) | Data::DateTimeIso(text) => text.trim(),
+        other => return other.as_f64(),
+    };
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let parsed = TIMESTAMP_FORMATS.iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| DATE_FORMATS.iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(text, format).ok())
+            .and_then(|date| date.and_hms_opt(0, 0, 0)))?;
+    Some((parsed - epoch).num_seconds() as f64 / 86_400.0)
+}
+
+fn get_cell_as_string(row: &[Data], col: usize) -> String {
+    // Short rows are treated as blank cells
     row.get(col)
-        .ok_or_else(|| "Column index out of bounds".to_string())
         .map(|cell| cell.to_string().trim().to_string())
+        .unwrap_or_default()
 }
 
 fn score_solution(solution: &Solution, all_people: &[Person]) -> i32 {
@@ -382,7 +607,7 @@ fn score_solution(solution: &Solution, all_people: &[Person]) -> i32 {
     
     for room in solution {
         for person_name in &room.members {
-            if let Some(person) = all_people.iter().find(|p| p.name == *person_name) {
+            if let Some(person) = all_people.iter().find(|p| p.id == *person_name) {
                 for choice in &person.choices {
                     if room.members.contains(choice) {
                         score += 1;
@@ -395,31 +620,42 @@ fn score_solution(solution: &Solution, all_people: &[Person]) -> i32 {
     score
 }
 
-fn validate_solution(solution: &Solution, all_people: &[Person]) -> Result<(), String> {
-    let mut problems = Vec::new();
-    
-    for room in solution {
-        for person_name in &room.members {
-            if let Some(person) = all_people.iter().find(|p| p.name == *person_name) {
-                let has_choice = person.choices.iter()
-                    .any(|choice| room.members.contains(choice));
-                
-                if !has_choice && !person.choices.is_empty() {
-                    problems.push(format!("{} has none of their {} choices in their room", 
-                                         person.name, person.choices.len()));
-                }
-            }
-        }
+/// Score components of one candidate solution
+#[derive(Debug, Clone, Copy)]
+pub struct Evaluation {
+    pub score: i32,
+    pub choice_score: i32,
+    pub without_choices: usize,
+    pub imbalance: usize,
+    pub penalty: i32,
+}
+
+/// The objective every search maximises: getting everyone a choice comes first, then
+/// satisfied choices, balanced rooms and soft rules
+pub fn objective(choice_score: i32, without_choices: usize, imbalance: usize, penalty: i32) -> i32 {
+    if without_choices == 0 {
+        choice_score  - (imbalance as i32 * 1000) - penalty
+    } else {
+        choice_score * 10 - (without_choices as i32 * 1000000) - (imbalance as i32 * 10) - penalty
     }
+}
+
+pub fn evaluate_solution(solution: &Solution, people: &[Person], constraints: &Constraints) -> Evaluation {
+    let choice_score = score_solution(solution, people);
+    let imbalance = calculate_imbalance(solution);
+    let without_choices = count_people_without_choices(solution, people);
+    let penalty = constraints.soft_penalty(solution, people);
     
-    if problems.is_empty() {
-        Ok(())
-    } else {
-        Err(format!("Solution has problems:\n  {}", problems.join("\n  ")))
+    Evaluation {
+        score: objective(choice_score, without_choices, imbalance, penalty),
+        choice_score,
+        without_choices,
+        imbalance,
+        penalty,
     }
 }
 
-fn calculate_imbalance(solution: &Solution) -> usize {
+pub fn calculate_imbalance(solution: &Solution) -> usize {
     let mut imbalance = 0;
     
     let mut by_category: HashMap<String, Vec<usize>> = HashMap::new();
@@ -439,176 +675,489 @@ fn calculate_imbalance(solution: &Solution) -> usize {
     imbalance
 }
 
-fn print_solution(solution: &Solution) {
-    println!("\n=== Room Assignments ===");
+use rayon::prelude::*;
+
+/// Why a pool's search stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Used all its iterations or generations
+    Finished,
+    /// Ran out of wall-clock time
+    TimeLimit,
+    /// Went `stall_iterations` without finding anything better
+    Stalled,
+    /// Everyone has a choice and the rooms are balanced
+    TargetReached,
+    /// The solve was cancelled from the UI
+    Cancelled,
+}
+
+/// Least time between two `progress` events, however fast the searches report
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Shared by every pool's search: reports overall progress to the UI, keeps each pool's
+/// best solution so far whichever strategy found it, and decides when searches should
+/// stop early
+pub struct SearchProgress<'a> {
+    /// Window to send progress events to; `None` sends none
+    app_handle: Option<&'a tauri::AppHandle>,
+    job: &'a SolveJob,
+    total: usize,
+    done: AtomicUsize,
+    started: Instant,
+    /// Greedy constructions that produced a valid solution, and ones that got stuck
+    valid: AtomicUsize,
+    invalid: AtomicUsize,
+    /// How often each person could not be placed, even after repair
+    stuck: Mutex<HashMap<String, usize>>,
+    last_report: Mutex<Option<Instant>>,
+    /// Searches stop once this much time has passed; progress is then the larger of the
+    /// time and iteration fractions
+    time_budget: Option<(Instant, Duration)>,
+    stall_iterations: Option<usize>,
+    stop_at_target: bool,
+    pairing: Pairing,
+    best: Mutex<HashMap<String, (Solution, Evaluation, Strategy)>>,
+    /// Every improvement to each pool's best, in order
+    history: Mutex<HashMap<String, Vec<ConvergencePoint>>>,
+}
+
+impl<'a> SearchProgress<'a> {
+    fn new(app_handle: Option<&'a tauri::AppHandle>, job: &'a SolveJob, total: usize, options: &SolveOptions) -> SearchProgress<'a> {
+        SearchProgress {
+            app_handle,
+            job,
+            total: total.max(1),
+            done: AtomicUsize::new(0),
+            started: Instant::now(),
+            valid: AtomicUsize::new(0),
+            invalid: AtomicUsize::new(0),
+            stuck: Mutex::new(HashMap::new()),
+            last_report: Mutex::new(None),
+            time_budget: options.time_limit_secs.map(|secs| (job.started, Duration::from_secs(secs))),
+            stall_iterations: options.stall_iterations,
+            stop_at_target: options.stop_at_target,
+            pairing: options.pairing,
+            best: Mutex::new(HashMap::new()),
+            history: Mutex::new(HashMap::new()),
+        }
+    }
     
-    let mut by_category: HashMap<String, Vec<&Room>> = HashMap::new();
-    for room in solution {
-        by_category.entry(room.category.clone())
-            .or_insert(Vec::new())
-            .push(room);
+    /// Searches run until `budget` has passed from now instead of for a number of
+    /// iterations, or until the time limit since the solve started if that comes first
+    fn timed(app_handle: Option<&'a tauri::AppHandle>, job: &'a SolveJob, budget: Duration, options: &SolveOptions) -> SearchProgress<'a> {
+        let budget = job.started.elapsed() + budget;
+        let budget = match options.time_limit_secs {
+            Some(secs) => budget.min(Duration::from_secs(secs)),
+            None => budget,
+        };
+        SearchProgress {
+            time_budget: Some((job.started, budget)),
+            ..SearchProgress::new(app_handle, job, usize::MAX, options)
+        }
     }
     
-    for (category, rooms) in by_category {
-        println!("\n{} rooms:", category);
-        
-        for (i, room) in rooms.iter().enumerate() {
-            println!("  Room {} - {} people:", i + 1, room.members.len());
-            for member in &room.members {
-                println!("    - {}", member);
+    /// Progress for a search run without a window, e.g. by the tests
+    #[cfg(test)]
+    pub fn unreported(job: &'a SolveJob, total: usize, options: &SolveOptions) -> SearchProgress<'a> {
+        SearchProgress::new(None, job, total, options)
+    }
+    
+    /// Phase 1 every greedy construction in this solve uses
+    pub fn pairing(&self) -> Pairing {
+        self.pairing
+    }
+    
+    fn out_of_time(&self) -> bool {
+        self.time_budget.is_some_and(|(start, budget)| start.elapsed() >= budget)
+    }
+    
+    /// Checked between chunks of work: `Some` once a search should stop, given its best so
+    /// far and how many iterations it has gone without improving
+    pub fn stop_reason(&self, best: Option<&Evaluation>, since_improvement: usize) -> Option<StopReason> {
+        if self.job.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if self.out_of_time() {
+            Some(StopReason::TimeLimit)
+        } else if self.stop_at_target && best.is_some_and(|eval| eval.without_choices == 0 && eval.imbalance == 0) {
+            Some(StopReason::TargetReached)
+        } else if self.stall_iterations.is_some_and(|limit| since_improvement >= limit) {
+            Some(StopReason::Stalled)
+        } else {
+            None
+        }
+    }
+    
+    /// Counts greedy constructions, with the ID of the person each failed one got stuck
+    /// on; call before `advance` for the iterations they used
+    pub fn constructed(&self, valid: usize, stuck_on: &[String]) {
+        self.valid.fetch_add(valid, Ordering::Relaxed);
+        self.invalid.fetch_add(stuck_on.len(), Ordering::Relaxed);
+        if !stuck_on.is_empty() {
+            let mut stuck = self.stuck.lock().unwrap();
+            for id in stuck_on {
+                *stuck.entry(id.clone()).or_default() += 1;
             }
         }
     }
-}
-fn analyze_constraints(people: &[Person]) {
-    println!("\n=== Constraint Analysis ===");
     
-    for person in people {
-        if person.choices.is_empty() {
-            continue;
+    pub fn advance(&self, iterations: usize) {
+        self.done.fetch_add(iterations, Ordering::Relaxed);
+        
+        let mut last_report = self.last_report.lock().unwrap();
+        if last_report.is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        *last_report = Some(Instant::now());
+        drop(last_report);
+        
+        self.report(None);
+    }
+    
+    /// Emits `progress` with the iterations done, construction counts, throughput, an ETA
+    /// and the best so far summed over every pool
+    fn report(&self, percent: Option<u32>) {
+        let done = self.done.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let per_sec = if elapsed > 0.0 { done as f64 / elapsed } else { 0.0 };
+        
+        // Timed searches end at the budget; the rest when their iterations run out
+        let by_iterations = (self.total != usize::MAX && per_sec > 0.0)
+            .then(|| self.total.saturating_sub(done) as f64 / per_sec);
+        let by_time = self.time_budget
+            .map(|(start, budget)| budget.saturating_sub(start.elapsed()).as_secs_f64());
+        let eta = match (by_iterations, by_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        
+        let mut fraction = done.min(self.total) as f64 / self.total as f64;
+        if let Some((start, budget)) = self.time_budget {
+            fraction = fraction.max(start.elapsed().as_secs_f64() / budget.as_secs_f64().max(0.001));
         }
+        let percent = percent.unwrap_or((fraction.min(1.0) * 100.0) as u32);
+        self.job.set_percent(percent);
         
-        // Check if any of their choices are in the same category
-        let same_category_choices: Vec<&String> = person.choices.iter()
-            .filter(|choice| {
-                people.iter().any(|p| &p.name == *choice && p.category == person.category)
+        let best = self.best.lock().unwrap();
+        let best_so_far = (!best.is_empty()).then(|| {
+            let evals = || best.values().map(|(_, eval, _)| eval);
+            json!({
+                "pools": best.len(),
+                "choice_score": evals().map(|e| e.choice_score).sum::<i32>(),
+                "without_choices": evals().map(|e| e.without_choices).sum::<usize>(),
+                "imbalance": evals().map(|e| e.imbalance).sum::<usize>(),
+                "penalty": evals().map(|e| e.penalty).sum::<i32>(),
             })
+        });
+        drop(best);
+        
+        let valid = self.valid.load(Ordering::Relaxed);
+        let invalid = self.invalid.load(Ordering::Relaxed);
+        let failure_rate = if valid + invalid > 0 { invalid as f64 / (valid + invalid) as f64 } else { 0.0 };
+        
+        let mut most_stuck: Vec<(String, usize)> = self.stuck.lock().unwrap()
+            .iter()
+            .map(|(id, count)| (id.clone(), *count))
             .collect();
+        most_stuck.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        most_stuck.truncate(5);
         
-        if same_category_choices.is_empty() {
-            println!("WARNING: {} has no choices in their category ({})", person.name, person.category);
+        if let Some(app_handle) = self.app_handle {
+            let _ = app_handle.emit_to("main", "progress", json!({
+                "job_id": self.job.id,
+                "percent": percent,
+                "iterations": done,
+                "valid": valid,
+                "invalid": invalid,
+                "failure_rate": failure_rate,
+                "most_stuck": most_stuck,
+                "iterations_per_sec": per_sec,
+                "elapsed_secs": elapsed,
+                "eta_secs": eta,
+                "best": best_so_far
+            }));
         }
-        
-        // Check if any choices avoid them or are avoided by them
-        let mut blocked_choices = 0;
-        for choice_name in &person.choices {
-            if let Some(choice_person) = people.iter().find(|p| &p.name == choice_name) {
-                if choice_person.avoids.contains(&person.name) {
-                    blocked_choices += 1;
-                }
-            }
+    }
+    
+    /// Offers a pool's solution; if it beats the best from every strategy so far it is kept,
+    /// the UI told and `true` returned
+    pub fn improved(&self, pool: &str, strategy: Strategy, iteration: usize, solution: &Solution, eval: &Evaluation) -> bool {
+        let mut best = self.best.lock().unwrap();
+        if best.get(pool).is_some_and(|(_, current, _)| current.score >= eval.score) {
+            return false;
         }
+        best.insert(pool.to_string(), (solution.clone(), *eval, strategy));
+        self.history.lock().unwrap().entry(pool.to_string()).or_default().push(ConvergencePoint {
+            iteration,
+            elapsed_secs: self.started.elapsed().as_secs_f64(),
+            strategy,
+            score: eval.score,
+            choice_score: eval.choice_score,
+            without_choices: eval.without_choices,
+            imbalance: eval.imbalance,
+            soft_penalty: eval.penalty,
+        });
+        drop(best);
         
-        if blocked_choices > 0 {
-            println!("Note: {}/{} of {}'s choices avoid them", 
-                     blocked_choices, person.choices.len(), person.name);
+        if let Some(app_handle) = self.app_handle {
+            let _ = app_handle.emit_to("main", "solution_update", json!({
+                "job_id": self.job.id,
+                "category": pool,
+                "strategy": strategy,
+                "iteration": iteration,
+                "choice_score": eval.choice_score,
+                "without_choices": eval.without_choices,
+                "imbalance": eval.imbalance,
+                "total_score": eval.score
+            }));
+        }
+        true
+    }
+    
+    /// Best solution any strategy has found for `pool`, and which one found it
+    pub fn best(&self, pool: &str) -> Option<(Solution, Evaluation, Strategy)> {
+        self.best.lock().unwrap().get(pool).cloned()
+    }
+    
+    /// Like `best`, without copying the rooms
+    pub fn best_eval(&self, pool: &str) -> Option<Evaluation> {
+        self.best.lock().unwrap().get(pool).map(|(_, eval, _)| *eval)
+    }
+    
+    /// Pools that stopped early leave the bar short of 100
+    fn finish(self) -> SearchLog {
+        self.report(Some(100));
+        let valid = self.valid.into_inner();
+        let invalid = self.invalid.into_inner();
+        SearchLog {
+            convergence: self.history.into_inner().unwrap(),
+            secs: self.started.elapsed().as_secs_f64(),
+            placement_failures: self.stuck.into_inner().unwrap(),
+            failure_rate: if valid + invalid > 0 { invalid as f64 / (valid + invalid) as f64 } else { 0.0 },
         }
     }
 }
 
+/// What a finished search records for the result
+#[derive(Default)]
+struct SearchLog {
+    convergence: HashMap<String, Vec<ConvergencePoint>>,
+    secs: f64,
+    placement_failures: HashMap<String, usize>,
+    failure_rate: f64,
+}
 
-use rayon::prelude::*;
+/// What the heuristic search came up with for one pool
+pub struct PoolOutcome {
+    pub best: Option<(Solution, Evaluation)>,
+    /// Random-search iterations, or solutions built by the other strategies
+    pub iterations: usize,
+    /// Strategy that found `best`
+    pub strategy: Strategy,
+    pub stop_reason: StopReason,
+}
 
-fn random_search(
+/// Random greedy sampling for one pool of rooms. Runs fewer iterations than asked when
+/// `progress` says to stop early; the outcome says how many ran and why it stopped.
+///
+/// `iterations` are also the seeds, so a second round starting where the first stopped
+/// explores new solutions, and `best` carries over whatever an earlier round found.
+pub fn random_search(
+    pool: &str,
     people: &[Person],
-    target_distributions: &HashMap<String, RoomDistribution>,
-    num_iterations: usize,
-    app_handle: &tauri::AppHandle,
-) -> Option<Solution> {
-    // Pre-compute mutual friend counts ONCE
-    let mutual_counts: HashMap<String, usize> = people.iter()
-        .map(|person| {
-            let count = person.choices.iter()
-                .filter(|choice_name| {
-                    people.iter().any(|other| 
-                        &other.name == *choice_name && 
-                        other.category == person.category &&
-                        other.choices.contains(&person.name)
-                    )
-                })
-                .count();
-            (person.name.clone(), count)
-        })
-        .collect();
+    distribution: &RoomDistribution,
+    constraints: &Constraints,
+    iterations: Range<usize>,
+    mut best: Option<(Solution, Evaluation)>,
+    progress: &SearchProgress,
+) -> PoolOutcome {
+    // Mutual friend counts are computed ONCE, here
+    let mut builder = SolutionBuilder::new(pool, people, distribution, constraints)
+        .pairing(progress.pairing());
     
-    println!("Running {} iterations in parallel across {} threads...", 
-             num_iterations, rayon::current_num_threads());
+    println!("{}: running {} iterations in parallel across {} threads...", 
+             pool, iterations.len(), rayon::current_num_threads());
     
     // Run iterations in parallel chunks for better progress reporting
     let chunk_size = 1000;
-    let num_chunks = (num_iterations + chunk_size - 1) / chunk_size;
-    
-    let mut best_solution: Option<Solution> = None;
-    let mut best_score = i32::MIN;
-    let mut best_without_choices = usize::MAX;
+    let num_chunks = iterations.len().div_ceil(chunk_size);
+    let mut done = 0;
+    let mut since_improvement = 0;
+    let mut stop_reason = StopReason::Finished;
     
     for chunk_idx in 0..num_chunks {
-        let start_iter = chunk_idx * chunk_size;
-        let end_iter = ((chunk_idx + 1) * chunk_size).min(num_iterations);
-        // Emit progress
-      let progress = ((end_iter as f32 / num_iterations as f32) * 100.0) as u32;
-let _ = app_handle.emit_to("main", "progress", progress);      
+        if let Some(reason) = progress.stop_reason(best.as_ref().map(|(_, eval)| eval), since_improvement) {
+            println!("  {}: stopping after {} iterations ({:?})", pool, done, reason);
+            stop_reason = reason;
+            break;
+        }
+        
+        let start_iter = iterations.start + chunk_idx * chunk_size;
+        let end_iter = (start_iter + chunk_size).min(iterations.end);
 
 // Learn from best solution every 5 chunks
-        let pair_hints = if chunk_idx > 0 && chunk_idx % 20 == 0 {
-            if let Some(ref best) = best_solution {
-                extract_successful_pairs(best, people)
+        builder.set_pair_hints(if chunk_idx > 0 && chunk_idx % 20 == 0 {
+            if let Some((ref solution, _)) = best {
+                extract_successful_pairs(solution, people)
             } else {
                 HashMap::new()
             }
         } else {
             HashMap::new()
-        };
+        });
         
-        // Process chunk in parallel
+        // Process chunk in parallel, noting who each failed construction got stuck on
+        let stuck_on = Mutex::new(Vec::new());
         let chunk_result = (start_iter..end_iter)
             .into_par_iter()
             .filter_map(|iteration| {
-                generate_random_solution_fast(
-                    people,
-                    target_distributions,
-                    &mutual_counts,
-                    &pair_hints,
-                    iteration
-                )
+                match builder.build(iteration) {
+                    Ok(solution) => Some(solution),
+                    Err(id) => {
+                        stuck_on.lock().unwrap().push(id);
+                        None
+                    }
+                }
             })
             .map(|solution| {
-                let choice_score = score_solution(&solution, people);
-                let imbalance = calculate_imbalance(&solution);
-                let without_choices = count_people_without_choices(&solution, people);
-                
-                let score = if without_choices == 0 {
-                    choice_score  - (imbalance as i32 * 1000)
-                } else {
-                    choice_score * 10 - (without_choices as i32 * 1000000) - (imbalance as i32 * 10)
-                };
-                
-                (solution, score, choice_score, without_choices, imbalance)
+                let eval = evaluate_solution(&solution, people, constraints);
+                (solution, eval)
             })
-            .max_by_key(|(_, score, _, _, _)| *score);
+            .max_by_key(|(_, eval)| eval.score);
+        
+        let stuck_on = stuck_on.into_inner().unwrap();
+        progress.constructed(end_iter - start_iter - stuck_on.len(), &stuck_on);
+        progress.advance(end_iter - start_iter);
+        done += end_iter - start_iter;
+        since_improvement += end_iter - start_iter;
         
         // Update best solution from this chunk
-        if let Some((solution, score, choice_score, without_choices, imbalance)) = chunk_result {
-            if score > best_score {
-    println!("  New best: score={}, choice_score={}, imbalance={}, without_choices={}", 
-             score, choice_score, imbalance, without_choices);
-    
-    best_score = score;
-    best_without_choices = without_choices;
-    best_solution = Some(solution);
-    
- // Emit update
-let _ = app_handle.emit_to("main", "solution_update", json!({
-    "iteration": end_iter,
-    "choice_score": choice_score,
-    "without_choices": without_choices,
-    "imbalance": imbalance,
-    "total_score": score
-}));
+        if let Some((solution, eval)) = chunk_result {
+            let Evaluation { score, choice_score, without_choices, imbalance, .. } = eval;
+            let improved = match &best {
+                Some((_, b)) => score > b.score,
+                None => true,
+            };
+            if improved {
+    println!("  {} new best: score={}, choice_score={}, imbalance={}, without_choices={}", 
+             pool, score, choice_score, imbalance, without_choices);
+    
+    progress.improved(pool, Strategy::RandomSearch, end_iter, &solution, &eval);
+    best = Some((solution, eval));
+    since_improvement = 0;
     
     if without_choices == 0 {
-        println!("  Found perfect solution where everyone gets a choice!");
+        println!("  {}: found perfect solution where everyone gets a choice!", pool);
     }
 }
         }
         
         if chunk_idx % 5 == 0 && chunk_idx > 0 {
-            println!("  Completed {} iterations... (best so far: {} without choices)", 
-                     end_iter, best_without_choices);
+            println!("  {}: completed {} iterations... (best so far: {} without choices)", 
+                     pool, end_iter, best.as_ref().map_or(usize::MAX, |(_, b)| b.without_choices));
         }
     }
     
-    best_solution
+    PoolOutcome {
+        best,
+        iterations: done,
+        strategy: Strategy::RandomSearch,
+        stop_reason,
+    }
+}
+
+/// Random search over every pool at once. A quarter of the budget is shared out by pool
+/// size; the rest goes to the pools furthest from done. Each pool starts from its best in
+/// `progress`, if it has one, so starting rooms are only replaced by something better.
+fn adaptive_random_search(
+    heuristic_pools: &[&(String, Vec<Person>)],
+    target_distributions: &HashMap<String, RoomDistribution>,
+    upper_bounds: &HashMap<String, i32>,
+    constraints: &Constraints,
+    num_iterations: usize,
+    progress: &SearchProgress,
+) -> Vec<PoolOutcome> {
+    // First round: a quarter of the budget, shared by pool size, to find out which pools are hard
+    let pool_sizes: Vec<f64> = heuristic_pools.iter().map(|(_, pool_people)| pool_people.len() as f64).collect();
+    let first_round = allocate_iterations(&pool_sizes, num_iterations / 4);
+    
+    let found: Vec<PoolOutcome> = heuristic_pools.par_iter()
+        .zip(first_round.par_iter())
+        .map(|((category, pool_people), &iterations)| {
+            let start = progress.best(category).map(|(rooms, eval, _)| (rooms, eval));
+            random_search(category, pool_people, &target_distributions[category], constraints,
+                          0..iterations, start, progress)
+        })
+        .collect();
+    
+    // Second round: the rest of the budget goes to the pools furthest from done, and
+    // none to pools that already stopped early
+    let difficulty: Vec<f64> = heuristic_pools.iter()
+        .zip(&found)
+        .map(|((category, pool_people), outcome)| match outcome.stop_reason {
+            StopReason::Finished => search_difficulty(outcome.best.as_ref().map(|(_, eval)| eval),
+                                                      upper_bounds[category], pool_people.len()),
+            _ => 0.0,
+        })
+        .collect();
+    let second_round = allocate_iterations(&difficulty, num_iterations - first_round.iter().sum::<usize>());
+    
+    println!("Iterations per pool: {:?}", heuristic_pools.iter()
+        .zip(first_round.iter().zip(&second_round))
+        .map(|((category, _), (first, second))| (category.as_str(), first + second))
+        .collect::<Vec<_>>());
+    
+    heuristic_pools.par_iter()
+        .zip(first_round.par_iter().zip(second_round.par_iter()))
+        .zip(found.into_par_iter())
+        .map(|(((category, pool_people), (&first, &iterations)), outcome)| {
+            if iterations == 0 || outcome.stop_reason != StopReason::Finished {
+                return outcome;
+            }
+            let more = random_search(category, pool_people, &target_distributions[category], constraints,
+                                     first..first + iterations, outcome.best, progress);
+            PoolOutcome {
+                iterations: outcome.iterations + more.iterations,
+                ..more
+            }
+        })
+        .collect()
+}
+
+/// How much a pool would still gain from more iterations; 0 once it has everyone
+/// placed with a choice, balanced rooms, no soft penalty and the choice-score bound met
+fn search_difficulty(best: Option<&Evaluation>, upper_bound: i32, num_people: usize) -> f64 {
+    match best {
+        // Nothing valid yet, so this pool needs the most help
+        None => num_people as f64 * 10.0,
+        Some(eval) => {
+            eval.without_choices as f64 * 10.0
+                + (upper_bound - eval.choice_score).max(0) as f64
+                + eval.imbalance as f64
+                + if eval.penalty > 0 { 1.0 } else { 0.0 }
+        }
+    }
+}
+
+/// Splits `budget` iterations in proportion to `weights`, any rounding left over going
+/// to the heaviest
+fn allocate_iterations(weights: &[f64], budget: usize) -> Vec<usize> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return vec![0; weights.len()];
+    }
+    
+    let mut shares: Vec<usize> = weights.iter()
+        .map(|w| ((w / total) * budget as f64).floor() as usize)
+        .collect();
+    
+    let leftover = budget.saturating_sub(shares.iter().sum::<usize>());
+    if let Some(heaviest) = (0..weights.len()).max_by(|&a, &b| weights[a].total_cmp(&weights[b])) {
+        shares[heaviest] += leftover;
+    }
+    
+    shares
 }
 
 fn extract_successful_pairs(solution: &Solution, people: &[Person]) -> HashMap<(String, String), i32> {
@@ -622,8 +1171,8 @@ fn extract_successful_pairs(solution: &Solution, people: &[Person]) -> HashMap<(
                 let person2_name = &room.members[j];
                 
                 // Check if this is a mutual pair
-                if let Some(person1) = people.iter().find(|p| &p.name == person1_name) {
-                    if let Some(person2) = people.iter().find(|p| &p.name == person2_name) {
+                if let Some(person1) = people.iter().find(|p| &p.id == person1_name) {
+                    if let Some(person2) = people.iter().find(|p| &p.id == person2_name) {
                         let is_mutual = person1.choices.contains(person2_name) && 
                                        person2.choices.contains(person1_name);
                         
@@ -646,201 +1195,1004 @@ fn extract_successful_pairs(solution: &Solution, people: &[Person]) -> HashMap<(
     pairs
 }
 
-fn generate_random_solution_fast(
-    people: &[Person],
-    target_distributions: &HashMap<String, RoomDistribution>,
-    mutual_counts: &HashMap<String, usize>,
-    pair_hints: &HashMap<(String, String), i32>,
-    seed: usize,
-) -> Option<Solution> {
-    let mut rng = rand::rngs::StdRng::seed_from_u64(seed as u64);
-    let mut solution = Vec::new();
-    
-    for (category, dist) in target_distributions {
-        for &size in &dist.sizes {
-            solution.push(Room::new(category.clone(), size));
+/// How to handle the same name appearing on more than one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Keep the most recent response (by the Timestamp column, or the last row)
+    #[default]
+    KeepLatest,
+    /// Keep the earliest response in the sheet
+    KeepFirst,
+    /// Combine the choices and avoids of every response
+    Merge,
+    /// Refuse to solve and list every duplicate
+    Fail,
+}
+
+/// Which search handles pools that aren't solved exactly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Independent greedy constructions, keeping the best
+    #[default]
+    RandomSearch,
+    /// A population of solutions bred by combining whole rooms; see `genetic.rs`
+    Genetic,
+    /// Hill climbing from greedy starting points; see `local_search.rs`
+    LocalSearch,
+    /// All of the above racing for a fixed time; see `portfolio.rs`
+    Portfolio,
+}
+
+/// Settings passed from the UI alongside room size and iterations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolveOptions {
+    pub duplicate_policy: DuplicatePolicy,
+    /// Attribute rules, one per line; see `constraints.rs` for the syntax
+    pub room_rules: String,
+    /// Objective cost each time someone shares a room with a PreferNot entry
+    pub prefer_not_penalty: i32,
+    pub leader_policy: LeaderPolicy,
+    /// Groups of categories that may share rooms, one per line; see `constraints.rs`
+    pub category_compatibility: String,
+    /// Solve small categories with branch-and-bound instead of sampling
+    pub exact: bool,
+    /// Search nodes per category before the exact solver gives up
+    pub exact_node_budget: usize,
+    pub strategy: Strategy,
+    /// Solutions kept per pool by the genetic strategy
+    pub population_size: usize,
+    /// Rounds of breeding per pool; replaces iterations for the genetic strategy
+    pub generations: usize,
+    /// How long the portfolio strategy races; replaces iterations
+    pub time_budget_secs: u64,
+    /// Stop any strategy after this many seconds, keeping the best so far
+    pub time_limit_secs: Option<u64>,
+    /// Stop a pool's search after this many iterations without an improvement
+    pub stall_iterations: Option<usize>,
+    /// Stop a pool's search once everyone has a choice and its rooms are balanced
+    pub stop_at_target: bool,
+    /// How greedy constructions place people who chose each other
+    pub pairing: Pairing,
+}
+
+impl Default for SolveOptions {
+    fn default() -> SolveOptions {
+        SolveOptions {
+            duplicate_policy: DuplicatePolicy::default(),
+            room_rules: String::new(),
+            prefer_not_penalty: 5,
+            leader_policy: LeaderPolicy::default(),
+            category_compatibility: String::new(),
+            exact: false,
+            exact_node_budget: exact::DEFAULT_NODE_BUDGET,
+            strategy: Strategy::default(),
+            population_size: genetic::DEFAULT_POPULATION,
+            generations: genetic::DEFAULT_GENERATIONS,
+            time_budget_secs: portfolio::DEFAULT_TIME_BUDGET_SECS,
+            time_limit_secs: None,
+            stall_iterations: None,
+            stop_at_target: false,
+            pairing: Pairing::default(),
         }
     }
-    
-    let mut placed: HashSet<String> = HashSet::new();
-    
-    // Phase 1: Place mutual pairs, prioritizing those in pair_hints
-    let mut people_list: Vec<&Person> = people.iter().collect();
-    
-    if  seed % 2 == 0 {
-        people_list.sort_by_key(|p| -(mutual_counts.get(&p.name).copied().unwrap_or(0) as i32));
-    } else {
-        people_list.shuffle(&mut rng);
-    }
-    
-    for person in &people_list {
-        if placed.contains(&person.name) {
-            continue;
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SolveResult {
+    pub choice_score: i32,
+    pub imbalance: usize,
+    pub without_choices: usize,
+    pub total_rooms: usize,
+    /// Rooms as lists of person IDs; look names up in `people`
+    pub rooms_by_category: HashMap<String, Vec<Vec<String>>>,
+    /// How many people each room was planned for, in the same order as `rooms_by_category`;
+    /// a hand edit that overfills a room raises its size
+    #[serde(default)]
+    pub room_sizes: HashMap<String, Vec<usize>>,
+pub people: Vec<Person>,
+    #[serde(default)]
+    pub warnings: Vec<ValidationIssue>,
+    /// Objective points lost to soft room rules and PreferNot entries
+    #[serde(default)]
+    pub soft_penalty: i32,
+    #[serde(default)]
+    pub rule_violations: Vec<RuleViolation>,
+    #[serde(default)]
+    pub soft_avoid_violations: Vec<SoftAvoidViolation>,
+    /// Every category was solved exactly, so no better assignment exists
+    #[serde(default)]
+    pub proven_optimal: bool,
+    /// Categories solved exactly
+    #[serde(default)]
+    pub optimal_categories: Vec<String>,
+    /// No assignment can have a higher choice score than this
+    #[serde(default)]
+    pub upper_bound: i32,
+    /// Percentage of the upper bound the choice score falls short by
+    #[serde(default)]
+    pub optimality_gap: f64,
+    #[serde(default)]
+    pub category_bounds: HashMap<String, CategoryBound>,
+    #[serde(default)]
+    pub category_stats: HashMap<String, CategoryStats>,
+    /// Keyed by person ID
+    #[serde(default)]
+    pub person_stats: HashMap<String, PersonStats>,
+    /// Pools whose starting rooms couldn't be used, and why
+    #[serde(default)]
+    pub warm_start_issues: Vec<String>,
+    /// Each searched pool's best score over time; a last improvement long before the
+    /// end means more iterations are unlikely to help
+    #[serde(default)]
+    pub convergence: HashMap<String, Vec<ConvergencePoint>>,
+    /// How long the search ran, not counting the exact solver
+    #[serde(default)]
+    pub search_secs: f64,
+    /// Keyed by person ID: how many greedy constructions got stuck on them, even after
+    /// trying to make space
+    #[serde(default)]
+    pub placement_failures: HashMap<String, usize>,
+    /// Share of greedy constructions thrown away because someone couldn't be placed
+    #[serde(default)]
+    pub construction_failure_rate: f64,
+    /// What `verify_assignment` finds wrong with the rooms; empty after a solve, but hand
+    /// edits may break the rules
+    #[serde(default)]
+    pub violations: Vec<Violation>,
+    /// Keyed by person ID, for everyone without a choice: why each choice isn't in their room
+    #[serde(default)]
+    pub explanations: HashMap<String, Vec<ChoiceObstacle>>,
+}
+
+impl SolveResult {
+    /// Works every score, violation and per-person record out again from
+    /// `rooms_by_category`, after the rooms were edited by hand. The `edited` pools are
+    /// no longer known to be optimal.
+    pub fn rescore(&mut self, constraints: &Constraints, edited: &[String]) {
+        let mut solution: Solution = Vec::new();
+        for (category, rooms) in &self.rooms_by_category {
+            for (room_idx, members) in rooms.iter().enumerate() {
+                let planned = self.planned_size(category, room_idx).unwrap_or(constraints.max_room_size);
+                solution.push(Room {
+                    category: category.clone(),
+                    members: members.clone(),
+                    max_size: planned.max(members.len()),
+                });
+            }
         }
+        let people = &self.people;
         
-        // Sort choices by hint score (if we have hints)
-        let mut choices_with_scores: Vec<(&String, i32)> = person.choices.iter()
-            .map(|choice_name| {
-                let pair = if &person.name < choice_name {
-                    (person.name.clone(), choice_name.clone())
-                } else {
-                    (choice_name.clone(), person.name.clone())
-                };
-                let score = pair_hints.get(&pair).copied().unwrap_or(0);
-                (choice_name, score)
-            })
-            .collect();
-        
-        choices_with_scores.sort_by_key(|(_, score)| -score);
+        self.choice_score = score_solution(&solution, people);
+        self.imbalance = calculate_imbalance(&solution);
+        self.without_choices = count_people_without_choices(&solution, people);
+        self.total_rooms = solution.len();
+        self.rule_violations = constraints.violations(&solution, people);
+        self.soft_avoid_violations = constraints.soft_avoid_violations(&solution, people);
+        self.soft_penalty = constraints.soft_penalty(&solution, people);
         
-        // Try to place with mutual friends, prioritizing hinted pairs
-        for (choice_name, _) in choices_with_scores {
-            if placed.contains(choice_name) {
-                continue;
-            }
+        for (category, pool_stats) in self.category_stats.iter_mut() {
+            let pool_rooms: Solution = solution.iter()
+                .filter(|r| &r.category == category)
+                .cloned()
+                .collect();
+            let eval = evaluate_solution(&pool_rooms, people, constraints);
             
-            if let Some(friend) = people.iter().find(|p| &p.name == choice_name) {
-                if friend.category == person.category && friend.choices.contains(&person.name) {
-                    let mut candidate_rooms: Vec<usize> = solution.iter()
-                        .enumerate()
-                        .filter(|(_, r)| r.category == person.category && 
-                                         r.members.len() + 2 <= r.max_size)
-                        .map(|(i, _)| i)
-                        .collect();
-                    
-                    candidate_rooms.shuffle(&mut rng);
-                    
-                    for room_idx in candidate_rooms {
-                        if can_add_person_to_room(person, &solution[room_idx], people) &&
-                           can_add_person_to_room(friend, &solution[room_idx], people) {
-                            solution[room_idx].add_person(person.name.clone());
-                            solution[room_idx].add_person(friend.name.clone());
-                            placed.insert(person.name.clone());
-                            placed.insert(friend.name.clone());
-                            break;
-                        }
-                    }
-                    
-                    if placed.contains(&person.name) {
-                        break;
-                    }
-                }
+            pool_stats.people = pool_rooms.iter().map(|r| r.members.len()).sum();
+            pool_stats.rooms = pool_rooms.len();
+            pool_stats.score = eval.score;
+            pool_stats.choice_score = eval.choice_score;
+            pool_stats.without_choices = eval.without_choices;
+            pool_stats.imbalance = eval.imbalance;
+            pool_stats.soft_penalty = eval.penalty;
+            pool_stats.mutual_pairs = extract_successful_pairs(&pool_rooms, people).len();
+            pool_stats.exact &= !edited.contains(category);
+            
+            if let Some(bound) = self.category_bounds.get_mut(category) {
+                *bound = CategoryBound::new(eval.choice_score, bound.upper_bound);
             }
         }
+        
+        self.optimal_categories.retain(|c| !edited.contains(c));
+        self.proven_optimal &= edited.is_empty();
+        self.optimality_gap = bounds::gap_percent(self.choice_score, self.upper_bound);
+        self.person_stats = stats::person_stats(&self.rooms_by_category, people);
+        self.violations = verify::verify_assignment(people, &self.rooms_by_category, constraints);
+        self.explanations = explain::explain_unhappy(self, constraints);
     }
     
-    // Phase 2: Place remaining people, preferring rooms with their choices
-    let mut remaining: Vec<&Person> = people.iter()
-        .filter(|p| !placed.contains(&p.name))
-        .collect();
-    remaining.shuffle(&mut rng);
+    /// Planned size of one room; `None` for results saved before sizes were kept
+    pub fn planned_size(&self, category: &str, room_idx: usize) -> Option<usize> {
+        self.room_sizes.get(category)
+            .and_then(|sizes| sizes.get(room_idx))
+            .copied()
+    }
+}
+
+/// Checks one pool's starting rooms (person IDs, e.g. from an earlier result) and turns
+/// them into a solution: everyone in the pool exactly once, no room over the size limit
+/// and no hard avoid or rule broken. The pool's planned `sizes` go to the fullest rooms
+/// first, so the search fills rooms as it would from scratch.
+fn starting_rooms(
+    pool: &str,
+    rooms: &[Vec<String>],
+    pool_people: &[Person],
+    sizes: &[usize],
+    max_room_size: usize,
+    constraints: &Constraints,
+) -> Result<Solution, String> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut solution = Vec::new();
     
-    for person in remaining {
-        let mut best_rooms: Vec<(usize, usize)> = Vec::new();
+    for (room_idx, members) in rooms.iter().enumerate() {
+        if members.len() > max_room_size {
+            return Err(format!("Room {} has {} people, more than {}", room_idx + 1, members.len(), max_room_size));
+        }
         
-        for (idx, room) in solution.iter().enumerate() {
-            if room.category != person.category || !room.has_space() {
-                continue;
-            }
-            
-            if !can_add_person_to_room(person, room, people) {
-                continue;
+        let mut people_in_room: Vec<&Person> = Vec::with_capacity(members.len());
+        for id in members {
+            let Some(person) = pool_people.iter().find(|p| &p.id == id) else {
+                return Err(format!("'{}' in Room {} is not in {}", id, room_idx + 1, pool));
+            };
+            if !seen.insert(id.as_str()) {
+                return Err(format!("{} is in more than one room", person.name));
             }
-            
-            let choice_count = person.choices.iter()
-                .filter(|choice| room.members.contains(choice))
-                .count();
-            
-            best_rooms.push((idx, choice_count));
+            people_in_room.push(person);
         }
         
-        if best_rooms.is_empty() {
-            return None;
+        // Checked as a full room, leaders first so one-per-room sees them before everyone else
+        people_in_room.sort_by_key(|p| !constraints::is_leader(p));
+        let mut room = Room::new(pool.to_string(), members.len());
+        for person in people_in_room {
+            if !can_add_person_to_room(person, &room, pool_people, constraints) {
+                return Err(format!("{} breaks an avoid or room rule in Room {}", person.name, room_idx + 1));
+            }
+            room.add_person(person.id.clone());
         }
         
-        best_rooms.sort_by_key(|(_, count)| -(*count as i32));
-        
-        let max_count = best_rooms[0].1;
-        let top_rooms: Vec<usize> = best_rooms.iter()
-            .filter(|(_, count)| *count == max_count)
-            .map(|(idx, _)| *idx)
-            .collect();
-        
-        let room_idx = top_rooms[rng.gen_range(0..top_rooms.len())];
-        solution[room_idx].add_person(person.name.clone());
-        placed.insert(person.name.clone());
+        if !room.members.is_empty() {
+            solution.push(room);
+        }
+    }
+    
+    if let Some(missing) = pool_people.iter().find(|p| !seen.contains(p.id.as_str())) {
+        return Err(format!("{} is not in any room", missing.name));
     }
     
-    Some(solution)
+    // A room holding more than its planned size keeps its people; rooms beyond the plan
+    // may fill up to the limit
+    let mut planned = sizes.to_vec();
+    planned.sort_by(|a, b| b.cmp(a));
+    let mut fullest_first: Vec<usize> = (0..solution.len()).collect();
+    fullest_first.sort_by_key(|&i| std::cmp::Reverse(solution[i].members.len()));
+    for (rank, room_idx) in fullest_first.into_iter().enumerate() {
+        let room = &mut solution[room_idx];
+        room.max_size = planned.get(rank).copied().unwrap_or(max_room_size).max(room.members.len());
+    }
+    
+    Ok(solution)
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct SolveResult {
-    pub choice_score: i32,
-    pub imbalance: usize,
-    pub without_choices: usize,
-    pub total_rooms: usize,
-    pub rooms_by_category: HashMap<String, Vec<Vec<String>>>,
-pub people: Vec<Person>,
+/// Reads the uploaded spreadsheet straight from its bytes, failing on any error-level issue
+fn load_people(file_bytes: &[u8], options: &SolveOptions) -> Result<(Vec<Person>, Vec<ValidationIssue>), String> {
+    let (people, issues) = read_spreadsheet(file_bytes, options.duplicate_policy)
+        .map_err(|e| format!("Failed to read spreadsheet: {}", e))?;
+    
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    if issues.iter().any(|issue| issue.is_error()) {
+        return Err(validation::format_errors(&issues));
+    }
+    
+    Ok((people, issues))
 }
 
-pub fn solve_from_bytes(
+/// Checks rooms made anywhere (another tool, an old export, a hand-edited file) against the
+/// spreadsheet and the options they should satisfy
+pub fn verify_from_bytes(
     file_bytes: Vec<u8>,
     max_room_size: usize,
-    num_iterations: usize,
-    app_handle: &tauri::AppHandle,
-) -> Result<SolveResult, String> {
-    // Write bytes to temporary file
-    use std::io::Write;
-    let temp_path = std::env::temp_dir().join("roomies_temp.xlsx");
-    let mut file = std::fs::File::create(&temp_path)
-        .map_err(|e| format!("Failed to create temp file: {}", e))?;
-    file.write_all(&file_bytes)
-        .map_err(|e| format!("Failed to write temp file: {}", e))?;
-    drop(file);
-    
-    // Read spreadsheet
-    let people = read_spreadsheet(temp_path.to_str().unwrap())
-        .map_err(|e| format!("Failed to read spreadsheet: {}", e))?;
-    
-    // Count people per category
+    options: &SolveOptions,
+    rooms: &HashMap<String, Vec<Vec<String>>>,
+) -> Result<Vec<Violation>, String> {
+    let (people, _) = load_people(&file_bytes, options)?;
+    let mut constraints = Constraints::from_options(options, &people)?;
+    constraints.max_room_size = max_room_size;
+    Ok(verify::verify_assignment(&people, rooms, &constraints))
+}
+
+/// Room sizes for each pool; under one leader per room, each leader gets a room
+fn target_distributions(
+    people: &[Person],
+    constraints: &Constraints,
+    max_room_size: usize,
+) -> Result<HashMap<String, RoomDistribution>, String> {
+    // Count people per room pool (just their category unless categories may mix)
     let mut category_counts: HashMap<String, usize> = HashMap::new();
-    for person in &people {
-        *category_counts.entry(person.category.clone()).or_insert(0) += 1;
+    for person in people {
+        *category_counts.entry(constraints.pool_for(person).to_string()).or_insert(0) += 1;
     }
     
     // Calculate target distributions
     let mut target_distributions: HashMap<String, RoomDistribution> = HashMap::new();
     for (category, count) in &category_counts {
-        let dist = RoomDistribution::new(*count, max_room_size);
+        let dist = if constraints.leader_policy == LeaderPolicy::OnePerRoom {
+            // One room per leader, so there have to be enough leaders to cover the rooms
+            let leaders = people.iter()
+                .filter(|p| constraints.pool_for(p) == category && constraints::is_leader(p))
+                .count();
+            let rooms_needed = count.div_ceil(max_room_size);
+            if leaders < rooms_needed {
+                return Err(format!("{} needs at least {} leaders for one per room, but has {}",
+                                   category, rooms_needed, leaders));
+            }
+            RoomDistribution::with_min_rooms(*count, max_room_size, leaders)
+        } else {
+            RoomDistribution::new(*count, max_room_size)
+        };
         target_distributions.insert(category.clone(), dist);
     }
     
-    // Run solver
-     let solution = random_search(&people, &target_distributions, num_iterations, app_handle)
-        .ok_or("No valid solution found")?;
+    Ok(target_distributions)
+}
+
+pub fn solve_from_bytes(
+    file_bytes: Vec<u8>,
+    max_room_size: usize,
+    num_iterations: usize,
+    options: &SolveOptions,
+    initial_rooms: Option<&HashMap<String, Vec<Vec<String>>>>,
+    app_handle: &tauri::AppHandle,
+    job: &SolveJob,
+) -> Result<SolveResult, String> {
+    if max_room_size == 0 {
+        return Err("Room size must be at least 1".to_string());
+    }
+    
+    let (people, issues) = load_people(&file_bytes, options)?;
+    
+    let mut result = solve_people(people, max_room_size, num_iterations, options, initial_rooms, Some(app_handle), job)?;
+    result.warnings = issues;
+    Ok(result)
+}
+
+/// Solves for people already read from the spreadsheet, sending progress to `app_handle`
+/// when there is one
+fn solve_people(
+    people: Vec<Person>,
+    max_room_size: usize,
+    num_iterations: usize,
+    options: &SolveOptions,
+    initial_rooms: Option<&HashMap<String, Vec<Vec<String>>>>,
+    app_handle: Option<&tauri::AppHandle>,
+    job: &SolveJob,
+) -> Result<SolveResult, String> {
+    let mut constraints = Constraints::from_options(options, &people)?;
+    constraints.max_room_size = max_room_size;
+    
+    let target_distributions = target_distributions(&people, &constraints, max_room_size)?;
+    
+    // Pools never interact (choices, avoids and rules all stay inside one), so each is
+    // searched on its own and the results merged
+    let pools: Vec<(String, Vec<Person>)> = target_distributions.keys()
+        .map(|category| {
+            let pool_people: Vec<Person> = people.iter()
+                .filter(|p| constraints.pool_for(p) == category)
+                .cloned()
+                .collect();
+            (category.clone(), pool_people)
+        })
+        .collect();
+    
+    let upper_bounds: HashMap<String, i32> = pools.iter()
+        .map(|(category, pool_people)| {
+            let sizes = &target_distributions[category].sizes;
+            (category.clone(), bounds::choice_score_upper_bound(pool_people, sizes, &constraints))
+        })
+        .collect();
+    
+    // Rooms from an earlier run (or edited by hand) to improve on rather than start from scratch
+    let mut starting: HashMap<String, (Solution, Evaluation)> = HashMap::new();
+    let mut warm_start_issues: Vec<String> = Vec::new();
+    if let Some(initial_rooms) = initial_rooms {
+        for (category, pool_people) in &pools {
+            let Some(rooms) = initial_rooms.get(category) else {
+                warm_start_issues.push(format!("{}: no starting rooms, solved from scratch", category));
+                continue;
+            };
+            let sizes = &target_distributions[category].sizes;
+            match starting_rooms(category, rooms, pool_people, sizes, max_room_size, &constraints) {
+                Ok(rooms) => {
+                    let eval = evaluate_solution(&rooms, pool_people, &constraints);
+                    starting.insert(category.clone(), (rooms, eval));
+                }
+                Err(reason) => {
+                    warm_start_issues.push(format!("{}: starting rooms not used, {}", category, reason));
+                }
+            }
+        }
+        for issue in &warm_start_issues {
+            println!("{}", issue);
+        }
+    }
+    
+    // Small pools can be solved exactly; whatever isn't proven optimal goes to the random search
+    let mut solution: Solution = Vec::new();
+    let mut optimal_categories: Vec<String> = Vec::new();
+    let mut exact_candidates: HashMap<String, Solution> = HashMap::new();
+    let mut heuristic_pools: Vec<&(String, Vec<Person>)> = Vec::new();
+    let mut kept_start: HashSet<String> = HashSet::new();
+    
+    for pool in &pools {
+        let (category, pool_people) = pool;
+        let dist = &target_distributions[category];
+        
+        if !options.exact || pool_people.len() > exact::EXACT_MAX_PEOPLE {
+            heuristic_pools.push(pool);
+            continue;
+        }
+        
+        let outcome = exact::solve_exact(category, pool_people, &dist.sizes, &constraints, options.exact_node_budget);
+        match (outcome.solution, outcome.proven_optimal) {
+            (Some(rooms), true) => {
+                // Starting rooms that score as well are just as good, so they stay as they were
+                let score = evaluate_solution(&rooms, pool_people, &constraints).score;
+                match starting.remove(category) {
+                    Some((start, eval)) if eval.score >= score => {
+                        solution.extend(start);
+                        kept_start.insert(category.clone());
+                    }
+                    _ => solution.extend(rooms),
+                }
+                optimal_categories.push(category.clone());
+            }
+            (None, true) => {
+                return Err(format!("No valid room assignment exists for {}", category));
+            }
+            (candidate, false) => {
+                if let Some(rooms) = candidate {
+                    exact_candidates.insert(category.clone(), rooms);
+                }
+                heuristic_pools.push(pool);
+            }
+        }
+    }
+    
+    let mut iterations_used: HashMap<String, usize> = HashMap::new();
+    let mut search_log = SearchLog::default();
+    let mut stop_reasons: HashMap<String, StopReason> = HashMap::new();
+    let mut found_by: HashMap<String, Strategy> = HashMap::new();
+    
+    if !heuristic_pools.is_empty() {
+        let progress = match options.strategy {
+            Strategy::RandomSearch => SearchProgress::new(app_handle, job, num_iterations, options),
+            Strategy::Genetic => SearchProgress::new(app_handle, job,
+                options.population_size * (options.generations + 1) * heuristic_pools.len(), options),
+            Strategy::LocalSearch => SearchProgress::new(app_handle, job, num_iterations * heuristic_pools.len(), options),
+            Strategy::Portfolio => SearchProgress::timed(app_handle, job, Duration::from_secs(options.time_budget_secs), options),
+        };
+        
+        // Every strategy picks its starting point up from the shared best: the better of the
+        // starting rooms and whatever an unfinished exact search found
+        for (category, pool_people) in &heuristic_pools {
+            if let Some((rooms, eval)) = starting.get(category) {
+                progress.improved(category, options.strategy, 0, rooms, eval);
+            }
+            if let Some(rooms) = exact_candidates.get(category) {
+                let eval = evaluate_solution(rooms, pool_people, &constraints);
+                progress.improved(category, options.strategy, 0, rooms, &eval);
+            }
+        }
+        
+        let outcomes: Vec<PoolOutcome> = match options.strategy {
+            Strategy::RandomSearch => {
+                adaptive_random_search(&heuristic_pools, &target_distributions, &upper_bounds,
+                                       &constraints, num_iterations, &progress)
+            }
+            Strategy::Genetic => {
+                heuristic_pools.par_iter()
+                    .map(|(category, pool_people)| {
+                        genetic::evolve(category, pool_people, &target_distributions[category], &constraints,
+                                        options.population_size, options.generations, &progress)
+                    })
+                    .collect()
+            }
+            Strategy::LocalSearch => {
+                heuristic_pools.par_iter()
+                    .map(|(category, pool_people)| {
+                        local_search::search(category, pool_people, &target_distributions[category], &constraints,
+                                             num_iterations, 0, &progress)
+                    })
+                    .collect()
+            }
+            Strategy::Portfolio => {
+                heuristic_pools.par_iter()
+                    .map(|(category, pool_people)| {
+                        portfolio::race(category, pool_people, &target_distributions[category], &constraints,
+                                        options.population_size, &progress)
+                    })
+                    .collect()
+            }
+        };
+        search_log = progress.finish();
+        
+        for ((category, pool_people), outcome) in heuristic_pools.iter().zip(outcomes) {
+            iterations_used.insert(category.clone(), outcome.iterations);
+            stop_reasons.insert(category.clone(), outcome.stop_reason);
+            
+            // Starting rooms are only replaced by something strictly better, so running again
+            // never makes things worse; otherwise keep whichever of the heuristic search and
+            // the unfinished exact search did better
+            let mut best: Option<(Solution, i32, Option<Strategy>)> = starting.remove(category)
+                .map(|(rooms, eval)| (rooms, eval.score, None));
+            let mut from_start = best.is_some();
+            
+            let mut candidates = Vec::new();
+            if let Some((rooms, _)) = outcome.best {
+                candidates.push((rooms, Some(outcome.strategy)));
+            }
+            if let Some(rooms) = exact_candidates.remove(category) {
+                candidates.push((rooms, None));
+            }
+            
+            for (rooms, strategy) in candidates {
+                let score = evaluate_solution(&rooms, pool_people, &constraints).score;
+                let better = match &best {
+                    Some((_, best_score, _)) => score > *best_score,
+                    None => true,
+                };
+                if better {
+                    best = Some((rooms, score, strategy));
+                    from_start = false;
+                }
+            }
+            
+            let Some((rooms, _, strategy)) = best else {
+                return Err(format!("No valid solution found for {}", category));
+            };
+            if from_start {
+                kept_start.insert(category.clone());
+            }
+            if let Some(strategy) = strategy {
+                found_by.insert(category.clone(), strategy);
+            }
+            solution.extend(rooms);
+        }
+    }
+    
+    // Never hand back rooms that break the basics, whichever search produced them
+    let mut rooms_by_category: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+    let mut room_sizes: HashMap<String, Vec<usize>> = HashMap::new();
+    for room in &solution {
+        rooms_by_category.entry(room.category.clone()).or_default().push(room.members.clone());
+        room_sizes.entry(room.category.clone()).or_default().push(room.max_size);
+    }
+    let violations = verify::verify_assignment(&people, &rooms_by_category, &constraints);
+    if !violations.is_empty() {
+        return Err(verify::format_violations(&violations));
+    }
     
     // Calculate results
     let choice_score = score_solution(&solution, &people);
     let imbalance = calculate_imbalance(&solution);
     let without_choices = count_people_without_choices(&solution, &people);
     let total_rooms = solution.len();
+    let rule_violations = constraints.violations(&solution, &people);
+    let soft_avoid_violations = constraints.soft_avoid_violations(&solution, &people);
+    let soft_penalty = constraints.soft_penalty(&solution, &people);
     
-    // Group rooms by category
-    let mut rooms_by_category: HashMap<String, Vec<Vec<String>>> = HashMap::new();
-    for room in &solution {
-        rooms_by_category
-            .entry(room.category.clone())
-            .or_insert(Vec::new())
-            .push(room.members.clone());
+    // Per-pool results, and how far each could still improve
+    let mut category_stats: HashMap<String, CategoryStats> = HashMap::new();
+    let mut category_bounds: HashMap<String, CategoryBound> = HashMap::new();
+    for (category, pool_people) in &pools {
+        let pool_rooms: Solution = solution.iter()
+            .filter(|r| &r.category == category)
+            .cloned()
+            .collect();
+        let eval = evaluate_solution(&pool_rooms, pool_people, &constraints);
+        
+        category_bounds.insert(category.clone(), CategoryBound::new(eval.choice_score, upper_bounds[category]));
+        category_stats.insert(category.clone(), CategoryStats {
+            people: pool_people.len(),
+            rooms: pool_rooms.len(),
+            iterations: iterations_used.get(category).copied().unwrap_or(0),
+            strategy: found_by.get(category).copied(),
+            stop_reason: stop_reasons.get(category).copied(),
+            kept_starting_rooms: kept_start.contains(category),
+            exact: optimal_categories.contains(category),
+            score: eval.score,
+            choice_score: eval.choice_score,
+            without_choices: eval.without_choices,
+            imbalance: eval.imbalance,
+            soft_penalty: eval.penalty,
+            mutual_pairs: extract_successful_pairs(&pool_rooms, pool_people).len(),
+        });
     }
+    let upper_bound: i32 = category_bounds.values().map(|b| b.upper_bound).sum();
+    let optimality_gap = bounds::gap_percent(choice_score, upper_bound);
     
-    Ok(SolveResult {
+    let person_stats = stats::person_stats(&rooms_by_category, &people);
+    
+    let mut result = SolveResult {
         choice_score,
         imbalance,
         without_choices,
         total_rooms,
         rooms_by_category,
+        room_sizes,
         people: people.clone(),
-    })
-}
\ No newline at end of file
+        warnings: Vec::new(),
+        soft_penalty,
+        rule_violations,
+        soft_avoid_violations,
+        proven_optimal: heuristic_pools.is_empty(),
+        optimal_categories,
+        upper_bound,
+        optimality_gap,
+        category_bounds,
+        category_stats,
+        person_stats,
+        warm_start_issues,
+        convergence: search_log.convergence,
+        search_secs: search_log.secs,
+        placement_failures: search_log.placement_failures,
+        construction_failure_rate: search_log.failure_rate,
+        violations: Vec::new(),
+        explanations: HashMap::new(),
+    };
+    result.explanations = explain::explain_unhappy(&result, &constraints);
+    
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::SolveJobs;
+    use crate::testing::{constraints, person, ring_of_boys, staff_example};
+    use crate::validation::Severity;
+
+    /// A response on spreadsheet `row`, sent at `timestamp` when the sheet has one
+    fn submission(id: &str, row: usize, timestamp: Option<f64>, choices: &[&str]) -> Submission {
+        Submission { person: person(id, "Boys", choices), row, timestamp }
+    }
+
+    fn resolve(submissions: Vec<Submission>, policy: DuplicatePolicy) -> (Vec<Person>, Vec<ValidationIssue>) {
+        let mut issues = Vec::new();
+        let people = resolve_duplicates(submissions, policy, "Sheet1", 0, &mut issues);
+        (people, issues)
+    }
+
+    fn choices(people: &[Person], id: &str) -> Vec<String> {
+        people.iter().find(|p| p.id == id).unwrap().choices.clone()
+    }
+
+    #[test]
+    fn single_responses_pass_through_in_sheet_order() {
+        let (people, issues) = resolve(vec![
+            submission("b2", 2, None, &[]),
+            submission("b1", 3, None, &[]),
+        ], DuplicatePolicy::Fail);
+
+        assert_eq!(people.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec!["b2", "b1"]);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn keep_latest_goes_by_timestamp_before_row() {
+        let (people, issues) = resolve(vec![
+            submission("b1", 2, Some(200.0), &["b2"]),
+            submission("b2", 3, None, &[]),
+            submission("b1", 4, Some(100.0), &["b3"]),
+        ], DuplicatePolicy::KeepLatest);
+
+        assert_eq!(people.len(), 2);
+        assert_eq!(choices(&people, "b1"), vec!["b2"]);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].severity, issues[0].row), (Severity::Warning, 4));
+    }
+
+    #[test]
+    fn keep_first_goes_by_row() {
+        let (people, issues) = resolve(vec![
+            submission("b1", 2, Some(200.0), &["b2"]),
+            submission("b1", 3, Some(100.0), &["b3"]),
+        ], DuplicatePolicy::KeepFirst);
+
+        assert_eq!(choices(&people, "b1"), vec!["b2"]);
+        assert_eq!(issues.iter().map(|i| i.row).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn merge_combines_choices_latest_first_and_drops_avoided_ones() {
+        let mut earlier = submission("b1", 2, None, &["b2", "b4"]);
+        earlier.person.avoids.push("b3".to_string());
+        let (people, issues) = resolve(vec![
+            earlier,
+            submission("b1", 3, None, &["b3", "b2"]),
+        ], DuplicatePolicy::Merge);
+
+        assert_eq!(people.len(), 1);
+        assert_eq!(choices(&people, "b1"), vec!["b2", "b4"]);
+        assert_eq!(people[0].avoids, vec!["b3"]);
+        assert!(issues.iter().all(|i| !i.is_error()));
+    }
+
+    #[test]
+    fn merge_keeps_the_latest_attributes_and_fills_gaps_from_earlier_ones() {
+        let mut earlier = submission("b1", 2, None, &[]);
+        earlier.person.attributes.insert("School".to_string(), "North".to_string());
+        earlier.person.attributes.insert("Shirt".to_string(), "M".to_string());
+        let mut latest = submission("b1", 3, None, &[]);
+        latest.person.attributes.insert("School".to_string(), "South".to_string());
+        let (people, _) = resolve(vec![earlier, latest], DuplicatePolicy::Merge);
+
+        let attributes = &people[0].attributes;
+        assert_eq!(attributes["School"], "South");
+        assert_eq!(attributes["Shirt"], "M");
+    }
+
+    #[test]
+    fn fail_reports_every_duplicate_row_as_an_error() {
+        let (_, issues) = resolve(vec![
+            submission("b1", 2, None, &[]),
+            submission("b2", 3, None, &[]),
+            submission("b1", 4, None, &[]),
+        ], DuplicatePolicy::Fail);
+
+        assert_eq!(issues.iter().map(|i| (i.is_error(), i.row)).collect::<Vec<_>>(), vec![(true, 2), (true, 4)]);
+    }
+
+    /// Two people called Sam Lee (IDs 7 and 8) and one Alex Kim (ID 9)
+    fn reference_lookup() -> (HashSet<String>, HashMap<String, Vec<String>>) {
+        let ids = ["7", "8", "9"].iter().map(|id| id.to_string()).collect();
+        let ids_by_name = HashMap::from([
+            ("Sam Lee".to_string(), vec!["7".to_string(), "8".to_string()]),
+            ("Alex Kim".to_string(), vec!["9".to_string()]),
+        ]);
+        (ids, ids_by_name)
+    }
+
+    #[test]
+    fn reference_resolves_by_id_or_unique_name() {
+        let (ids, ids_by_name) = reference_lookup();
+
+        assert_eq!(resolve_reference("8", &ids, &ids_by_name), Some("8".to_string()));
+        assert_eq!(resolve_reference("Alex Kim", &ids, &ids_by_name), Some("9".to_string()));
+    }
+
+    #[test]
+    fn shared_name_is_ambiguous_but_their_ids_are_not() {
+        let (ids, ids_by_name) = reference_lookup();
+
+        assert_eq!(resolve_reference("Sam Lee", &ids, &ids_by_name), None);
+        assert_eq!(resolve_reference("7", &ids, &ids_by_name), Some("7".to_string()));
+    }
+
+    #[test]
+    fn unknown_reference_is_kept_and_matches_no_one() {
+        let (ids, ids_by_name) = reference_lookup();
+
+        let unknown = resolve_reference("Sam Le", &ids, &ids_by_name).unwrap();
+        assert_eq!(unknown, "Sam Le");
+        assert!(!ids.contains(&unknown));
+    }
+
+    #[test]
+    fn text_timestamps_compare_with_real_dates() {
+        let serial = parse_timestamp(&Data::Float(45366.5)).unwrap();
+        let iso = parse_timestamp(&Data::String("2024-03-15 12:00:00".to_string())).unwrap();
+        let forms = parse_timestamp(&Data::String("3/15/2024 12:00:00".to_string())).unwrap();
+        let day_first = parse_timestamp(&Data::String("15/03/2024 12:00".to_string())).unwrap();
+
+        assert_eq!(iso, serial);
+        assert_eq!(forms, serial);
+        assert_eq!(day_first, serial);
+        assert!(parse_timestamp(&Data::String("3/16/2024 9:00:00".to_string())).unwrap() > serial);
+    }
+
+    #[test]
+    fn unreadable_or_blank_timestamps_are_missing() {
+        assert_eq!(parse_timestamp(&Data::String("yesterday".to_string())), None);
+        assert_eq!(parse_timestamp(&Data::Empty), None);
+    }
+
+    #[test]
+    fn keep_latest_is_the_default() {
+        assert_eq!(DuplicatePolicy::default(), DuplicatePolicy::KeepLatest);
+        assert_eq!(SolveOptions::default().duplicate_policy, DuplicatePolicy::KeepLatest);
+    }
+
+    #[test]
+    fn staff_example_fills_every_pool() {
+        let people = staff_example();
+        let constraints = constraints(&people, "Staff, *", 4);
+
+        let mut pools: HashMap<&str, Vec<Person>> = HashMap::new();
+        for person in &people {
+            pools.entry(constraints.pool_for(person)).or_default().push(person.clone());
+        }
+        assert_eq!(pools.len(), 2);
+
+        let mut rooms_by_category: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for (pool, pool_people) in &pools {
+            let distribution = RoomDistribution::new(pool_people.len(), 4);
+            assert_eq!(distribution.sizes, vec![3, 3]);
+            let solution = SolutionBuilder::new(pool, pool_people, &distribution, &constraints)
+                .build(0)
+                .unwrap();
+            rooms_by_category.insert(pool.to_string(), solution.into_iter().map(|r| r.members).collect());
+        }
+
+        assert_eq!(verify::verify_assignment(&people, &rooms_by_category, &constraints), vec![]);
+    }
+
+    #[test]
+    fn one_per_room_needs_a_leader_for_every_room() {
+        let mut people: Vec<Person> = (1..=9).map(|i| person(&format!("b{}", i), "Boys", &[])).collect();
+        for boy in &mut people[..2] {
+            boy.attributes.insert("Role".to_string(), "Leader".to_string());
+        }
+        let mut constraints = constraints(&people, "", 3);
+        constraints.leader_policy = LeaderPolicy::OnePerRoom;
+
+        assert_eq!(target_distributions(&people, &constraints, 3).unwrap_err(),
+                   "Boys needs at least 3 leaders for one per room, but has 2");
+
+        // More leaders than rooms needed means more, smaller rooms
+        for boy in &mut people[2..4] {
+            boy.attributes.insert("Role".to_string(), "Leader".to_string());
+        }
+        assert_eq!(target_distributions(&people, &constraints, 3).unwrap()["Boys"].sizes, vec![3, 2, 2, 2]);
+    }
+
+    #[test]
+    fn iteration_budget_adds_up_to_the_total() {
+        for (weights, budget) in [
+            (vec![1.0, 1.0, 1.0], 100),
+            (vec![7.0, 3.0, 0.5, 11.0], 9_999),
+            (vec![2.5], 17),
+            (vec![0.0, 4.0], 5),
+        ] {
+            let first = allocate_iterations(&weights, budget / 4);
+            let second = allocate_iterations(&weights, budget - first.iter().sum::<usize>());
+            assert_eq!(first.iter().chain(&second).sum::<usize>(), budget, "{:?}", weights);
+        }
+
+        let shares = allocate_iterations(&[1.0, 3.0], 100);
+        assert_eq!(shares, vec![25, 75]);
+        assert_eq!(allocate_iterations(&[0.0, 4.0], 5), vec![0, 5]);
+        assert_eq!(allocate_iterations(&[0.0, 0.0], 5), vec![0, 0]);
+    }
+
+    fn solve(people: &[Person], num_iterations: usize, options: &SolveOptions,
+             initial_rooms: Option<&HashMap<String, Vec<Vec<String>>>>) -> SolveResult {
+        let job = SolveJobs::default().start();
+        solve_people(people.to_vec(), 4, num_iterations, options, initial_rooms, None, &job).unwrap()
+    }
+
+    fn result_objective(result: &SolveResult) -> i32 {
+        objective(result.choice_score, result.without_choices, result.imbalance, result.soft_penalty)
+    }
+
+    #[test]
+    fn warm_start_never_does_worse_than_its_starting_rooms() {
+        let people = ring_of_boys(10);
+        let first = solve(&people, 1, &SolveOptions::default(), None);
+
+        for (exact, exact_node_budget) in [(false, 0), (true, 50), (true, exact::DEFAULT_NODE_BUDGET)] {
+            let options = SolveOptions { exact, exact_node_budget, ..Default::default() };
+            let again = solve(&people, 50, &options, Some(&first.rooms_by_category));
+
+            assert!(again.warm_start_issues.is_empty(), "{:?}", again.warm_start_issues);
+            assert!(result_objective(&again) >= result_objective(&first),
+                    "exact {} with {} nodes: {} < {}", exact, exact_node_budget,
+                    result_objective(&again), result_objective(&first));
+            let mut sizes = again.room_sizes["Boys"].clone();
+            sizes.sort();
+            assert_eq!(sizes, vec![3, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn starting_rooms_keep_the_planned_sizes() {
+        let people = ring_of_boys(10);
+        let constraints = constraints(&people, "", 4);
+        let ids = |range: std::ops::Range<usize>| range.map(|i| format!("b{}", i)).collect::<Vec<_>>();
+        let rooms = vec![ids(8..10), ids(0..4), ids(4..8)];
+
+        let solution = starting_rooms("Boys", &rooms, &people, &[4, 3, 3], 4, &constraints).unwrap();
+
+        // The two full rooms take the planned 4 and 3, but the second already holds 4
+        assert_eq!(solution.iter().map(|r| r.max_size).collect::<Vec<_>>(), vec![3, 4, 4]);
+    }
+
+    fn evaluation(without_choices: usize, imbalance: usize) -> Evaluation {
+        Evaluation { score: 0, choice_score: 0, without_choices, imbalance, penalty: 0 }
+    }
+
+    #[test]
+    fn stop_reason_reports_the_target_stalls_and_time() {
+        let job = SolveJobs::default().start();
+        let options = SolveOptions { stop_at_target: true, stall_iterations: Some(100), ..Default::default() };
+        let progress = SearchProgress::unreported(&job, 1_000, &options);
+
+        assert_eq!(progress.stop_reason(Some(&evaluation(0, 0)), 0), Some(StopReason::TargetReached));
+        assert_eq!(progress.stop_reason(Some(&evaluation(1, 0)), 99), None);
+        assert_eq!(progress.stop_reason(Some(&evaluation(0, 1)), 100), Some(StopReason::Stalled));
+
+        let options = SolveOptions { time_limit_secs: Some(0), ..Default::default() };
+        let progress = SearchProgress::unreported(&job, 1_000, &options);
+        assert_eq!(progress.stop_reason(None, 0), Some(StopReason::TimeLimit));
+    }
+
+    #[test]
+    fn time_limit_counts_from_when_the_solve_started() {
+        let job = SolveJobs::default().start();
+        let options = SolveOptions { time_limit_secs: Some(5), ..Default::default() };
+
+        let progress = SearchProgress::new(None, &job, 1_000, &options);
+        assert_eq!(progress.time_budget, Some((job.started, Duration::from_secs(5))));
+
+        let progress = SearchProgress::timed(None, &job, Duration::from_secs(60), &options);
+        assert_eq!(progress.time_budget, Some((job.started, Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn random_search_stops_at_the_target_or_when_it_stalls() {
+        // Mutual pairs can all be satisfied; in a ring someone always misses out
+        let pairs: Vec<Person> = (0..12)
+            .map(|i| person(&format!("b{}", i), "Boys", &[format!("b{}", i ^ 1).as_str()]))
+            .collect();
+        let ring = ring_of_boys(12);
+        let distribution = RoomDistribution { sizes: vec![4, 4, 4] };
+        let job = SolveJobs::default().start();
+
+        let options = SolveOptions { stop_at_target: true, ..Default::default() };
+        let progress = SearchProgress::unreported(&job, 20_000, &options);
+        let pair_constraints = constraints(&pairs, "", 4);
+        let outcome = random_search("Boys", &pairs, &distribution, &pair_constraints, 0..20_000, None, &progress);
+        assert_eq!(outcome.stop_reason, StopReason::TargetReached);
+        assert!(outcome.iterations < 20_000);
+        let (_, eval) = outcome.best.unwrap();
+        assert_eq!((eval.without_choices, eval.imbalance), (0, 0));
+
+        let options = SolveOptions { stall_iterations: Some(2_000), ..Default::default() };
+        let progress = SearchProgress::unreported(&job, 20_000, &options);
+        let ring_constraints = constraints(&ring, "", 4);
+        let outcome = random_search("Boys", &ring, &distribution, &ring_constraints, 0..20_000, None, &progress);
+        assert_eq!(outcome.stop_reason, StopReason::Stalled);
+        assert!(outcome.iterations < 20_000);
+    }
+
+    #[test]
+    fn construction_counts_follow_who_got_stuck() {
+        let job = SolveJobs::default().start();
+        let distribution = RoomDistribution { sizes: vec![2, 2] };
+
+        // d avoids a: greedy placement sometimes gets stuck on d, and repair always recovers
+        let mut people: Vec<Person> = ["a", "b", "c", "d"].iter().map(|id| person(id, "Boys", &[])).collect();
+        people[3].avoids.push("a".to_string());
+        let repairable = constraints(&people, "", 2);
+        let progress = SearchProgress::unreported(&job, 200, &SolveOptions::default());
+        let outcome = random_search("Boys", &people, &distribution, &repairable, 0..200, None, &progress);
+        assert!(outcome.best.is_some());
+        let log = progress.finish();
+        assert!(log.placement_failures.is_empty());
+        assert_eq!(log.failure_rate, 0.0);
+
+        // With a, b and c all avoiding each other, one of them never fits
+        people[0].avoids = vec!["b".to_string(), "c".to_string()];
+        people[1].avoids = vec!["c".to_string()];
+        let impossible = constraints(&people, "", 2);
+        let progress = SearchProgress::unreported(&job, 200, &SolveOptions::default());
+        let outcome = random_search("Boys", &people, &distribution, &impossible, 0..200, None, &progress);
+        assert!(outcome.best.is_none());
+        let log = progress.finish();
+        assert_eq!(log.placement_failures.values().sum::<usize>(), 200);
+        assert_eq!(log.failure_rate, 1.0);
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::solver::{Person, StopReason, Strategy};
+
+/// How one category (or pool of mixed categories) was solved, searched separately
+/// from every other pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryStats {
+    pub people: usize,
+    pub rooms: usize,
+    /// Random-search iterations this pool was given (harder pools get more), or
+    /// solutions built by the other strategies
+    pub iterations: usize,
+    /// Strategy whose solution was kept; `None` when it came from the exact search
+    /// or the starting rooms
+    #[serde(default)]
+    pub strategy: Option<Strategy>,
+    /// Why the search stopped; `None` for pools solved exactly
+    #[serde(default)]
+    pub stop_reason: Option<StopReason>,
+    /// Nothing beat the starting rooms passed in, so they were kept as they were
+    #[serde(default)]
+    pub kept_starting_rooms: bool,
+    /// Solved by the exact search, so no better assignment exists
+    pub exact: bool,
+    pub score: i32,
+    pub choice_score: i32,
+    pub without_choices: usize,
+    pub imbalance: usize,
+    pub soft_penalty: i32,
+    /// Pairs in the same room who chose each other
+    #[serde(default)]
+    pub mutual_pairs: usize,
+}
+
+/// One improvement to a pool's best solution during the search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvergencePoint {
+    /// The iteration (or child, or move) of the strategy that found it
+    pub iteration: usize,
+    /// Seconds since the search started
+    pub elapsed_secs: f64,
+    pub strategy: Strategy,
+    pub score: i32,
+    pub choice_score: i32,
+    pub without_choices: usize,
+    pub imbalance: usize,
+    pub soft_penalty: i32,
+}
+
+/// How one person did in their room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonStats {
+    /// Room pool, the key into `rooms_by_category`
+    pub category: String,
+    /// Index of their room within the pool's rooms
+    pub room: usize,
+    pub choices_made: usize,
+    /// How many of their choices share their room
+    pub choices_received: usize,
+    /// 1 for their first choice; `None` when no choice shares their room
+    pub best_choice_rank: Option<usize>,
+    /// Roommates who chose them
+    pub chosen_by: usize,
+    /// Roommates they chose who also chose them
+    pub mutual_matches: usize,
+}
+
+impl PersonStats {
+    /// Made choices but got none of them
+    pub fn is_unhappy(&self) -> bool {
+        self.choices_made > 0 && self.choices_received == 0
+    }
+}
+
+/// Per-person records keyed by person ID, worked out from the final rooms
+pub fn person_stats(
+    rooms_by_category: &HashMap<String, Vec<Vec<String>>>,
+    people: &[Person],
+) -> HashMap<String, PersonStats> {
+    let by_id: HashMap<&str, &Person> = people.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut stats = HashMap::new();
+
+    for (category, rooms) in rooms_by_category {
+        for (room_idx, room) in rooms.iter().enumerate() {
+            for id in room {
+                let Some(person) = by_id.get(id.as_str()) else {
+                    continue;
+                };
+
+                let chosen_by_roommate = |roommate: &str| {
+                    roommate != id.as_str() && by_id.get(roommate).is_some_and(|r| r.choices.contains(id))
+                };
+
+                let received: Vec<&String> = person.choices.iter()
+                    .filter(|c| room.contains(c))
+                    .collect();
+
+                stats.insert(id.clone(), PersonStats {
+                    category: category.clone(),
+                    room: room_idx,
+                    choices_made: person.choices.len(),
+                    choices_received: received.len(),
+                    best_choice_rank: person.choices.iter().position(|c| room.contains(c)).map(|i| i + 1),
+                    chosen_by: room.iter().filter(|r| chosen_by_roommate(r)).count(),
+                    mutual_matches: received.iter().filter(|c| chosen_by_roommate(c)).count(),
+                });
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, result};
+
+    fn example() -> Vec<Person> {
+        vec![
+            person("b1", "Boys", &["b2", "b3"]),
+            person("b2", "Boys", &["b1"]),
+            person("b3", "Boys", &["b4", "b1"]),
+            person("b4", "Boys", &["b1"]),
+            person("b5", "Boys", &[]),
+        ]
+    }
+
+    #[test]
+    fn person_stats_follow_the_rooms() {
+        let rooms = HashMap::from([("Boys".to_string(), vec![
+            vec!["b1".to_string(), "b2".to_string(), "b3".to_string()],
+            vec!["b4".to_string(), "b5".to_string()],
+        ])]);
+        let stats = person_stats(&rooms, &example());
+
+        let b1 = &stats["b1"];
+        assert_eq!((b1.category.as_str(), b1.room), ("Boys", 0));
+        assert_eq!((b1.choices_made, b1.choices_received, b1.best_choice_rank), (2, 2, Some(1)));
+        assert_eq!((b1.chosen_by, b1.mutual_matches), (2, 2));
+
+        let b3 = &stats["b3"];
+        assert_eq!((b3.choices_received, b3.best_choice_rank), (1, Some(2)));
+        assert_eq!((b3.chosen_by, b3.mutual_matches), (1, 1));
+
+        let b4 = &stats["b4"];
+        assert_eq!((b4.room, b4.choices_received, b4.best_choice_rank, b4.chosen_by), (1, 0, None, 0));
+        assert!(b4.is_unhappy());
+        assert!(!stats["b5"].is_unhappy());
+    }
+
+    #[test]
+    fn category_stats_follow_the_rooms_after_rescoring() {
+        let people = example();
+        let constraints = constraints(&people, "", 3);
+        let mut result = result(people, &[("Boys", vec![vec!["b1", "b2", "b3"], vec!["b4", "b5"]], vec![3, 2])], &constraints);
+        result.category_stats.insert("Boys".to_string(), CategoryStats {
+            people: 0,
+            rooms: 0,
+            iterations: 10,
+            strategy: None,
+            stop_reason: None,
+            kept_starting_rooms: false,
+            exact: false,
+            score: 0,
+            choice_score: 0,
+            without_choices: 0,
+            imbalance: 0,
+            soft_penalty: 0,
+            mutual_pairs: 0,
+        });
+        result.rescore(&constraints, &[]);
+
+        let stats = &result.category_stats["Boys"];
+        assert_eq!((stats.people, stats.rooms, stats.iterations), (5, 2, 10));
+        assert_eq!(stats.choice_score, result.choice_score);
+        assert_eq!(stats.without_choices, 1);
+        assert_eq!(stats.mutual_pairs, 2);
+        let unhappy = result.person_stats.values().filter(|p| p.is_unhappy()).count();
+        assert_eq!(stats.without_choices, unhappy);
+    }
+}
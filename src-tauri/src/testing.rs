@@ -0,0 +1,112 @@
+//! Small builders shared by the unit tests
+
+use std::collections::{BTreeMap, HashMap};
+use crate::constraints::Constraints;
+use crate::solver::{Person, Solution, SolveOptions, SolveResult};
+
+/// Someone in `category` who chose `choices`, in order; their name is their ID
+pub fn person(id: &str, category: &str, choices: &[&str]) -> Person {
+    Person {
+        id: id.to_string(),
+        name: id.to_string(),
+        category: category.to_string(),
+        choices: choices.iter().map(|c| c.to_string()).collect(),
+        avoids: Vec::new(),
+        prefer_not: Vec::new(),
+        attributes: BTreeMap::new(),
+    }
+}
+
+/// Constraints from the given compatibility lines, rooms of at most `max_room_size`
+pub fn constraints(people: &[Person], category_compatibility: &str, max_room_size: usize) -> Constraints {
+    let options = SolveOptions {
+        category_compatibility: category_compatibility.to_string(),
+        ..Default::default()
+    };
+    let mut constraints = Constraints::from_options(&options, people).unwrap();
+    constraints.max_room_size = max_room_size;
+    constraints
+}
+
+/// The README's example: 5 Boys, 5 Girls and 2 Staff, with `Staff, *`. One staff member
+/// chose a boy; the other chose no one.
+pub fn staff_example() -> Vec<Person> {
+    let mut people: Vec<Person> = (1..=5)
+        .map(|i| person(&format!("b{}", i), "Boys", &[]))
+        .chain((1..=5).map(|i| person(&format!("g{}", i), "Girls", &[])))
+        .collect();
+    people.push(person("s1", "Staff", &["b1"]));
+    people.push(person("s2", "Staff", &[]));
+    people
+}
+
+/// `count` Boys in a ring, each choosing the next two along
+pub fn ring_of_boys(count: usize) -> Vec<Person> {
+    (0..count)
+        .map(|i| {
+            let choices = [format!("b{}", (i + 1) % count), format!("b{}", (i + 2) % count)];
+            person(&format!("b{}", i), "Boys", &[choices[0].as_str(), choices[1].as_str()])
+        })
+        .collect()
+}
+
+/// Panics unless everyone in `people` is in exactly one room of `solution`, and no one else is
+pub fn assert_everyone_placed_once(solution: &Solution, people: &[Person]) {
+    let mut placed: Vec<&str> = solution.iter()
+        .flat_map(|room| room.members.iter().map(|id| id.as_str()))
+        .collect();
+    placed.sort();
+    let mut expected: Vec<&str> = people.iter().map(|p| p.id.as_str()).collect();
+    expected.sort();
+    assert_eq!(placed, expected);
+}
+
+/// One pool's name, rooms of person IDs and the size each room was planned for
+pub type PoolRooms<'a> = (&'a str, Vec<Vec<&'a str>>, Vec<usize>);
+
+/// A result with the given rooms, each pool's rooms planned for `room_sizes`, scored
+/// under `constraints`
+pub fn result(
+    people: Vec<Person>,
+    rooms: &[PoolRooms],
+    constraints: &Constraints,
+) -> SolveResult {
+    let rooms_by_category: HashMap<String, Vec<Vec<String>>> = rooms.iter()
+        .map(|(pool, rooms, _)| (pool.to_string(), rooms.iter()
+            .map(|room| room.iter().map(|id| id.to_string()).collect())
+            .collect()))
+        .collect();
+    let room_sizes = rooms.iter()
+        .map(|(pool, _, sizes)| (pool.to_string(), sizes.clone()))
+        .collect();
+    let edited: Vec<String> = rooms_by_category.keys().cloned().collect();
+    let mut result = SolveResult {
+        choice_score: 0,
+        imbalance: 0,
+        without_choices: 0,
+        total_rooms: 0,
+        rooms_by_category,
+        room_sizes,
+        people,
+        warnings: Vec::new(),
+        soft_penalty: 0,
+        rule_violations: Vec::new(),
+        soft_avoid_violations: Vec::new(),
+        proven_optimal: false,
+        optimal_categories: Vec::new(),
+        upper_bound: 0,
+        optimality_gap: 0.0,
+        category_bounds: HashMap::new(),
+        category_stats: HashMap::new(),
+        person_stats: HashMap::new(),
+        warm_start_issues: Vec::new(),
+        convergence: HashMap::new(),
+        search_secs: 0.0,
+        placement_failures: HashMap::new(),
+        construction_failure_rate: 0.0,
+        violations: Vec::new(),
+        explanations: HashMap::new(),
+    };
+    result.rescore(constraints, &edited);
+    result
+}
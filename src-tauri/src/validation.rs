@@ -0,0 +1,114 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Blocks solving until the spreadsheet is fixed
+    Error,
+    /// Reported alongside the result, solving continues
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub sheet: String,
+    /// 1-based row number as shown in Excel
+    pub row: usize,
+    /// Column letter as shown in Excel (A, B, ..., AA)
+    pub column: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn error(sheet: &str, row: usize, col: usize, message: String) -> ValidationIssue {
+        ValidationIssue {
+            severity: Severity::Error,
+            sheet: sheet.to_string(),
+            row,
+            column: column_letter(col),
+            message,
+        }
+    }
+
+    pub fn warning(sheet: &str, row: usize, col: usize, message: String) -> ValidationIssue {
+        ValidationIssue {
+            severity: Severity::Warning,
+            sheet: sheet.to_string(),
+            row,
+            column: column_letter(col),
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+        write!(f, "{} at {}!{}{}: {}", label, self.sheet, self.column, self.row, self.message)
+    }
+}
+
+/// Converts a 0-based column index into an Excel column letter (0 -> A, 27 -> AB)
+pub fn column_letter(col: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = col;
+    loop {
+        letters.push((b'A' + (n % 26) as u8) as char);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Formats the hard errors of a report into a single message for the UI
+pub fn format_errors(issues: &[ValidationIssue]) -> String {
+    let errors: Vec<String> = issues.iter()
+        .filter(|issue| issue.is_error())
+        .map(|issue| issue.to_string())
+        .collect();
+
+    format!("Spreadsheet has {} error(s):\n  {}", errors.len(), errors.join("\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_lettered_as_in_excel() {
+        assert_eq!(column_letter(0), "A");
+        assert_eq!(column_letter(25), "Z");
+        assert_eq!(column_letter(26), "AA");
+        assert_eq!(column_letter(27), "AB");
+        assert_eq!(column_letter(701), "ZZ");
+        assert_eq!(column_letter(702), "AAA");
+    }
+
+    #[test]
+    fn issue_names_its_cell() {
+        let issue = ValidationIssue::error("Sheet1", 4, 2, "Name is blank".to_string());
+
+        assert_eq!(issue.to_string(), "Error at Sheet1!C4: Name is blank");
+    }
+
+    #[test]
+    fn only_errors_are_listed_as_blocking() {
+        let issues = vec![
+            ValidationIssue::warning("Sheet1", 2, 0, "Duplicate response ignored".to_string()),
+            ValidationIssue::error("Sheet1", 3, 1, "Category is blank".to_string()),
+        ];
+
+        assert_eq!(format_errors(&issues), "Spreadsheet has 1 error(s):\n  Error at Sheet1!B3: Category is blank");
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use crate::constraints::Constraints;
+use crate::solver::{Person, Room};
+
+/// Something wrong with a finished assignment. Rooms are numbered from 0 within their
+/// category (or pool), as in `rooms_by_category`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// Someone from the spreadsheet is in no room
+    Unplaced { person: String },
+    /// Someone is in more than one room
+    PlacedMoreThanOnce { person: String, times: usize },
+    /// A room lists an ID that isn't in the spreadsheet
+    UnknownPerson { category: String, room: usize, person: String },
+    OverCapacity { category: String, room: usize, size: usize, max_size: usize },
+    /// Someone is in another pool's room, or with a category they may not share with
+    WrongCategory { category: String, room: usize, person: String, person_category: String },
+    /// Two people share a room though `person` avoids `avoided`
+    HardAvoid { category: String, room: usize, person: String, avoided: String },
+    /// A hard room rule, or one leader per room, is broken
+    HardRule { category: String, room: usize, rule: String, amount: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Unplaced { person } => write!(f, "{} has no room", person),
+            Violation::PlacedMoreThanOnce { person, times } => write!(f, "{} is in {} rooms", person, times),
+            Violation::UnknownPerson { category, room, person } =>
+                write!(f, "{} room {} holds {}, who isn't in the spreadsheet", category, room + 1, person),
+            Violation::OverCapacity { category, room, size, max_size } =>
+                write!(f, "{} room {} has {} people, over the room size of {}", category, room + 1, size, max_size),
+            Violation::WrongCategory { category, room, person, person_category } =>
+                write!(f, "{} ({}) is in {} room {}", person, person_category, category, room + 1),
+            Violation::HardAvoid { category, room, person, avoided } =>
+                write!(f, "{} shares {} room {} with {}, whom they avoid", person, category, room + 1, avoided),
+            Violation::HardRule { category, room, rule, amount } =>
+                write!(f, "{} room {} breaks \"{}\" by {}", category, room + 1, rule, amount),
+        }
+    }
+}
+
+/// Checks an assignment without trusting whoever made it: every person placed exactly
+/// once, no room over the room size, categories kept apart unless they may mix, no hard
+/// avoid or hard rule broken. Works on rooms from the solver, from manual edits or from
+/// anywhere else.
+pub fn verify_assignment(
+    people: &[Person],
+    rooms: &HashMap<String, Vec<Vec<String>>>,
+    constraints: &Constraints,
+) -> Vec<Violation> {
+    let by_id: HashMap<&str, &Person> = people.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut times_placed: HashMap<&str, usize> = HashMap::new();
+    let mut violations = Vec::new();
+
+    // Sorted so the same rooms always give the same list
+    let mut categories: Vec<&String> = rooms.keys().collect();
+    categories.sort();
+
+    for category in categories {
+        let category_rooms = &rooms[category];
+        for (room_idx, room) in category_rooms.iter().enumerate() {
+            if constraints.max_room_size > 0 && room.len() > constraints.max_room_size {
+                violations.push(Violation::OverCapacity {
+                    category: category.clone(),
+                    room: room_idx,
+                    size: room.len(),
+                    max_size: constraints.max_room_size,
+                });
+            }
+
+            for id in room {
+                let Some(person) = by_id.get(id.as_str()) else {
+                    violations.push(Violation::UnknownPerson {
+                        category: category.clone(),
+                        room: room_idx,
+                        person: id.clone(),
+                    });
+                    continue;
+                };
+                *times_placed.entry(person.id.as_str()).or_default() += 1;
+
+                let mixes_badly = room.iter()
+                    .filter_map(|other| by_id.get(other.as_str()))
+                    .any(|other| !constraints.compatible(&person.category, &other.category));
+                // `*` categories may be in any pool
+                let wrong_pool = !constraints.compatible_with_all.contains(&person.category)
+                    && constraints.pool_of(&person.category) != category;
+                if wrong_pool || mixes_badly {
+                    violations.push(Violation::WrongCategory {
+                        category: category.clone(),
+                        room: room_idx,
+                        person: person.id.clone(),
+                        person_category: person.category.clone(),
+                    });
+                }
+
+                for avoided in person.avoids.iter().filter(|a| room.contains(a)) {
+                    violations.push(Violation::HardAvoid {
+                        category: category.clone(),
+                        room: room_idx,
+                        person: person.id.clone(),
+                        avoided: avoided.clone(),
+                    });
+                }
+            }
+        }
+
+        let solution: Vec<Room> = category_rooms.iter()
+            .map(|members| Room {
+                category: category.clone(),
+                members: members.clone(),
+                max_size: constraints.max_room_size.max(members.len()),
+            })
+            .collect();
+        violations.extend(constraints.hard_violations(&solution, people).into_iter()
+            .map(|v| Violation::HardRule { category: v.category, room: v.room, rule: v.rule, amount: v.amount }));
+    }
+
+    for person in people {
+        match times_placed.get(person.id.as_str()).copied().unwrap_or(0) {
+            1 => {}
+            0 => violations.push(Violation::Unplaced { person: person.id.clone() }),
+            times => violations.push(Violation::PlacedMoreThanOnce { person: person.id.clone(), times }),
+        }
+    }
+
+    violations
+}
+
+/// One message listing every violation, for returning as an error
+pub fn format_violations(violations: &[Violation]) -> String {
+    let lines: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    format!("The assignment has {} problem(s):\n  {}", violations.len(), lines.join("\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{constraints, person, staff_example};
+
+    fn rooms(pools: &[(&str, Vec<Vec<&str>>)]) -> HashMap<String, Vec<Vec<String>>> {
+        pools.iter()
+            .map(|(pool, rooms)| (pool.to_string(), rooms.iter()
+                .map(|room| room.iter().map(|id| id.to_string()).collect())
+                .collect()))
+            .collect()
+    }
+
+    fn boys_and_girls() -> Vec<Person> {
+        vec![
+            person("b1", "Boys", &[]),
+            person("b2", "Boys", &[]),
+            person("g1", "Girls", &[]),
+            person("g2", "Girls", &[]),
+        ]
+    }
+
+    #[test]
+    fn sound_assignment_has_no_violations() {
+        let people = boys_and_girls();
+        let constraints = constraints(&people, "", 2);
+        let rooms = rooms(&[("Boys", vec![vec!["b1", "b2"]]), ("Girls", vec![vec!["g1", "g2"]])]);
+
+        assert_eq!(verify_assignment(&people, &rooms, &constraints), vec![]);
+    }
+
+    #[test]
+    fn missing_repeated_and_unknown_people_are_reported() {
+        let people = boys_and_girls();
+        let constraints = constraints(&people, "", 2);
+        let rooms = rooms(&[("Boys", vec![vec!["b1", "x9"], vec!["b1"]]), ("Girls", vec![vec!["g1", "g2"]])]);
+
+        let violations = verify_assignment(&people, &rooms, &constraints);
+
+        assert!(violations.contains(&Violation::UnknownPerson {
+            category: "Boys".to_string(), room: 0, person: "x9".to_string(),
+        }));
+        assert!(violations.contains(&Violation::PlacedMoreThanOnce { person: "b1".to_string(), times: 2 }));
+        assert!(violations.contains(&Violation::Unplaced { person: "b2".to_string() }));
+        assert_eq!(violations.len(), 3);
+    }
+
+    #[test]
+    fn room_over_the_room_size_is_reported() {
+        let people = boys_and_girls();
+        let constraints = constraints(&people, "", 1);
+        let rooms = rooms(&[("Boys", vec![vec!["b1", "b2"]]), ("Girls", vec![vec!["g1"], vec!["g2"]])]);
+
+        let violations = verify_assignment(&people, &rooms, &constraints);
+
+        assert_eq!(violations, vec![Violation::OverCapacity {
+            category: "Boys".to_string(), room: 0, size: 2, max_size: 1,
+        }]);
+        assert_eq!(violations[0].to_string(), "Boys room 1 has 2 people, over the room size of 1");
+    }
+
+    #[test]
+    fn person_in_another_category_s_room_is_reported() {
+        let people = boys_and_girls();
+        let constraints = constraints(&people, "", 3);
+        let rooms = rooms(&[("Boys", vec![vec!["b1", "b2", "g1"]]), ("Girls", vec![vec!["g2"]])]);
+
+        let violations = verify_assignment(&people, &rooms, &constraints);
+
+        assert!(violations.iter().any(|v| matches!(v,
+            Violation::WrongCategory { person, category, .. } if person == "g1" && category == "Boys")));
+        assert!(violations.iter().all(|v| matches!(v, Violation::WrongCategory { .. })));
+    }
+
+    #[test]
+    fn hard_avoid_is_reported_for_whoever_avoids() {
+        let mut people = boys_and_girls();
+        people[0].avoids.push("b2".to_string());
+        let constraints = constraints(&people, "", 2);
+        let rooms = rooms(&[("Boys", vec![vec!["b1", "b2"]]), ("Girls", vec![vec!["g1", "g2"]])]);
+
+        assert_eq!(verify_assignment(&people, &rooms, &constraints), vec![Violation::HardAvoid {
+            category: "Boys".to_string(), room: 0, person: "b1".to_string(), avoided: "b2".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn wildcard_people_may_be_in_any_pool() {
+        let people = staff_example();
+        let constraints = constraints(&people, "Staff, *", 4);
+        let rooms = rooms(&[
+            ("Boys + Staff", vec![vec!["b1", "b2", "b3"], vec!["b4", "b5", "s2"]]),
+            ("Girls + Staff", vec![vec!["g1", "g2", "g3"], vec!["g4", "g5", "s1"]]),
+        ]);
+
+        assert_eq!(verify_assignment(&people, &rooms, &constraints), vec![]);
+    }
+}
@@ -2,6 +2,9 @@ let uploadedFile = null;
 let isProcessing = false;
 let currentResult = null;
 let currentEventName = null;
+let currentJobId = null;
+let currentOptions = null;
+let currentRoomSize = null;
 
 document.addEventListener('DOMContentLoaded', () => {
     console.log('Page loaded');
@@ -12,14 +15,37 @@ document.addEventListener('DOMContentLoaded', () => {
         uploadedFile = e.target.files[0];
         console.log('File selected:', uploadedFile?.name);
         document.getElementById('solveBtn').disabled = !uploadedFile;
+
+        // A different spreadsheet can't start from the old result
+        document.getElementById('warmStartOption').style.display = 'none';
+        document.getElementById('warmStart').checked = false;
+    });
+
+    // Genetic settings matter for the genetic and portfolio strategies, the time budget only for portfolio
+    document.getElementById('strategy').addEventListener('change', (e) => {
+        const strategy = e.target.value;
+        document.getElementById('geneticSettings').style.display =
+            strategy === 'genetic' || strategy === 'portfolio' ? 'block' : 'none';
+        document.getElementById('portfolioSettings').style.display =
+            strategy === 'portfolio' ? 'block' : 'none';
     });
 
     // Solve button handler
     document.getElementById('solveBtn').addEventListener('click', handleSolve);
     
-    // Stop button handler
-    document.getElementById('stopBtn').addEventListener('click', () => {
-        alert('Stop functionality coming soon! The current search will complete.');
+    // Stop button handler: the solve finishes with the best rooms found so far
+    document.getElementById('stopBtn').addEventListener('click', async () => {
+        if (currentJobId === null) return;
+        document.getElementById('stopBtn').disabled = true;
+        document.getElementById('stopBtn').textContent = 'Stopping...';
+        try {
+            await window.__TAURI_INTERNALS__.invoke('cancel_solve', { jobId: currentJobId });
+        } catch (error) {
+            console.error('Error:', error);
+            alert('Error: ' + error);
+            document.getElementById('stopBtn').disabled = false;
+            document.getElementById('stopBtn').textContent = 'Stop Search';
+        }
     });
     
     // Download button handler
@@ -36,11 +62,37 @@ async function handleSolve() {
     const roomSize = parseInt(document.getElementById('roomSize').value);
     const iterations = parseInt(document.getElementById('iterations').value);
     const eventName = document.getElementById('eventName').value || 'Room Assignments';
+    const options = {
+        duplicate_policy: document.getElementById('duplicatePolicy').value,
+        room_rules: document.getElementById('roomRules').value,
+        prefer_not_penalty: parseInt(document.getElementById('preferNotPenalty').value) || 0,
+        leader_policy: document.getElementById('leaderPolicy').value,
+        category_compatibility: document.getElementById('categoryCompatibility').value,
+        exact: document.getElementById('exactMode').checked,
+        strategy: document.getElementById('strategy').value,
+        population_size: parseInt(document.getElementById('populationSize').value) || 60,
+        generations: parseInt(document.getElementById('generations').value) || 300,
+        time_budget_secs: parseInt(document.getElementById('timeBudget').value) || 30,
+        time_limit_secs: parseInt(document.getElementById('timeLimit').value) || null,
+        stall_iterations: parseInt(document.getElementById('stallIterations').value) || null,
+        stop_at_target: document.getElementById('stopAtTarget').checked,
+        pairing: document.getElementById('pairing').value
+    };
+
+    // Improve on the last result instead of starting over
+    const initialRooms = currentResult && document.getElementById('warmStart').checked
+        ? currentResult.rooms_by_category
+        : null;
 
     console.log('Processing with room size:', roomSize, 'iterations:', iterations);
 
     let progressUnlisten = null;
     let solutionUnlisten = null;
+    let finishedUnlisten = null;
+    let listen = null;
+    let resolveFinished;
+    const finished = new Promise(resolve => { resolveFinished = resolve; });
+    currentJobId = null;
 
     try {
         const arrayBuffer = await uploadedFile.arrayBuffer();
@@ -57,11 +109,14 @@ async function handleSolve() {
         document.getElementById('progress').style.display = 'block';
         document.getElementById('results').style.display = 'none';
         document.getElementById('progressText').textContent = 'Starting...';
+        document.getElementById('progressStats').innerHTML = '';
         document.getElementById('progressFill').style.width = '0%';
+        document.getElementById('stopBtn').disabled = false;
+        document.getElementById('stopBtn').textContent = 'Stop Search';
 
-        // Listen for progress events
+        // Listen for progress events; each carries the job it belongs to
         try {
-            const listen = window.__TAURI__?.event?.listen || 
+            listen = window.__TAURI__?.event?.listen || 
                           window.__TAURI_INTERNALS__?.listen ||
                           window.__TAURI_IPC__?.listen;
             
@@ -71,18 +126,24 @@ async function handleSolve() {
                 progressUnlisten = await listen('progress', (event) => {
                     console.log('Progress:', event.payload);
                     const progress = event.payload;
-                    document.getElementById('progressFill').style.width = progress + '%';
-                    document.getElementById('progressText').textContent = `Processing... ${progress}%`;
+                    if (progress.job_id !== currentJobId) return;
+                    document.getElementById('progressFill').style.width = progress.percent + '%';
+                    showProgressStats(progress);
                 });
                 
                 solutionUnlisten = await listen('solution_update', (event) => {
                     console.log('Solution update:', event.payload);
                     const update = event.payload;
+                    if (update.job_id !== currentJobId) return;
                     document.getElementById('progressText').textContent = 
-                        `Iteration ${update.iteration}: Score ${update.choice_score}, ` +
+                        `${update.category} (${strategyName(update.strategy)}), iteration ${update.iteration}: Score ${update.choice_score}, ` +
                         `${update.without_choices} without choices, imbalance ${update.imbalance}`;
                 });
                 
+                finishedUnlisten = await listen('solve_finished', (event) => {
+                    if (event.payload.job_id === currentJobId) resolveFinished(event.payload);
+                });
+                
                 console.log('Event listeners set up successfully');
             } else {
                 console.log('No event listener API found, using indeterminate progress');
@@ -93,23 +154,40 @@ async function handleSolve() {
             document.getElementById('progressFill').classList.add('indeterminate');
         }
 
-        // Call Rust backend
+        // Start the solve in the background
         console.log('Calling Tauri invoke...');
-        const result = await window.__TAURI_INTERNALS__.invoke('solve_rooms', {
+        currentJobId = await window.__TAURI_INTERNALS__.invoke('start_solve', {
             fileData: bytes,
             roomSize: roomSize,
-            iterations: iterations
+            iterations: iterations,
+            options: options,
+            initialRooms: initialRooms
         });
+        console.log('Started solve', currentJobId);
+
+        // It may have finished before we knew its ID; without events, poll until it does
+        const early = await window.__TAURI_INTERNALS__.invoke('get_solve_status', { jobId: currentJobId });
+        if (early.state !== 'running') {
+            resolveFinished(early);
+        } else if (!listen) {
+            pollSolveStatus(currentJobId, resolveFinished);
+        }
+        const status = await finished;
 
         // Clean up listeners
         if (progressUnlisten) progressUnlisten();
         if (solutionUnlisten) solutionUnlisten();
+        if (finishedUnlisten) finishedUnlisten();
         
         document.getElementById('progressFill').classList.remove('indeterminate');
 
-        console.log('Got result:', result);
-        currentResult = result;
-        displayResults(result);
+        if (status.state === 'failed') throw status.error;
+
+        console.log('Got result:', status.result);
+        currentResult = status.result;
+        currentOptions = options;
+        currentRoomSize = roomSize;
+        displayResults(status.result);
         
     } catch (error) {
         console.error('Error:', error);
@@ -124,11 +202,23 @@ async function handleSolve() {
         
         if (progressUnlisten) progressUnlisten();
         if (solutionUnlisten) solutionUnlisten();
+        if (finishedUnlisten) finishedUnlisten();
     } finally {
         isProcessing = false;
+        currentJobId = null;
     }
 }
 
+async function pollSolveStatus(jobId, onFinished) {
+    const status = await window.__TAURI_INTERNALS__.invoke('get_solve_status', { jobId });
+    if (status.state !== 'running') {
+        onFinished(status);
+        return;
+    }
+    document.getElementById('progressText').textContent = `Processing... ${status.percent}%`;
+    setTimeout(() => pollSolveStatus(jobId, onFinished), 500);
+}
+
 async function handleDownload() {
     if (!currentResult) {
         alert('No results to download');
@@ -140,10 +230,14 @@ async function handleDownload() {
         document.getElementById('downloadBtn').textContent = 'Generating PDF...';
         
         const resultJson = JSON.stringify(currentResult);
+        const attributeColumns = Array.from(
+            document.querySelectorAll('#pdfAttributes input[type="checkbox"]:checked')
+        ).map(box => box.value);
         
         const filePath = await window.__TAURI_INTERNALS__.invoke('generate_pdf_report', {
             resultJson: resultJson,
-            eventName: currentEventName || 'Room Assignments'
+            eventName: currentEventName || 'Room Assignments',
+            attributeColumns: attributeColumns
         });
         
         alert('PDF saved to: ' + filePath);
@@ -156,8 +250,247 @@ async function handleDownload() {
     }
 }
 
+function formatDuration(secs) {
+    if (secs == null) return '—';
+    const rounded = Math.round(secs);
+    if (rounded < 60) return `${rounded}s`;
+    return `${Math.floor(rounded / 60)}m ${String(rounded % 60).padStart(2, '0')}s`;
+}
+
+// Live numbers under the progress bar
+function showProgressStats(progress) {
+    const attempts = progress.valid + progress.invalid;
+    const stats = [
+        ['Done', `${progress.percent}%`],
+        ['Iterations', progress.iterations.toLocaleString()],
+        ['Per second', Math.round(progress.iterations_per_sec).toLocaleString()],
+        ['Time left', formatDuration(progress.eta_secs)],
+        ['Failed builds', attempts ? `${Math.round(progress.failure_rate * 100)}%` : '—'],
+    ];
+    if (progress.most_stuck && progress.most_stuck.length > 0) {
+        stats.push(['Hardest to place', progress.most_stuck.map(([id]) => id).slice(0, 3).join(', ')]);
+    }
+    if (progress.best) {
+        stats.push(
+            ['Best score', progress.best.choice_score],
+            ['Without choices', progress.best.without_choices],
+            ['Imbalance', progress.best.imbalance],
+        );
+    }
+    document.getElementById('progressStats').innerHTML = stats
+        .map(([label, value]) => `
+            <div class="progress-stat">
+                <div class="progress-stat-value">${value}</div>
+                <div class="progress-stat-label">${label}</div>
+            </div>
+        `)
+        .join('');
+}
+
+function strategyName(strategy) {
+    return {
+        random_search: 'random search',
+        genetic: 'genetic',
+        local_search: 'local search',
+        portfolio: 'portfolio',
+    }[strategy] ?? 'exact';
+}
+
+function stopReasonNote(reason) {
+    return {
+        time_limit: 'stopped at the time limit',
+        stalled: 'stopped when it stopped improving',
+        target_reached: 'stopped at the target',
+        cancelled: 'stopped early',
+    }[reason] ?? '';
+}
+
+// Best choice score over the search as a step line, with the upper bound dashed, and
+// people without any of their choices as a second line scaled to the most there were.
+// Getting everyone a choice comes first, so the score can dip as that line drops.
+function convergenceChart(points, searchSecs, upperBound) {
+    if (!points || points.length === 0) return '';
+    const width = 300, height = 60;
+    const maxScore = Math.max(1, upperBound, ...points.map(p => p.choice_score));
+    const maxWithout = Math.max(...points.map(p => p.without_choices));
+    const maxSecs = Math.max(searchSecs, 0.001);
+    const x = secs => (Math.min(secs / maxSecs, 1) * width).toFixed(1);
+    const y = score => (height - Math.max(score, 0) / maxScore * height).toFixed(1);
+    const yWithout = count => (height - count / Math.max(maxWithout, 1) * height).toFixed(1);
+
+    const steps = value => {
+        const line = points.flatMap((p, i) => i === 0
+            ? [`${x(p.elapsed_secs)},${value(p)}`]
+            : [`${x(p.elapsed_secs)},${value(points[i - 1])}`, `${x(p.elapsed_secs)},${value(p)}`]);
+        line.push(`${x(searchSecs)},${value(points[points.length - 1])}`);
+        return line.join(' ');
+    };
+
+    const last = points[points.length - 1];
+    let title = `${points.length} improvements, the last after ${last.elapsed_secs.toFixed(1)}s of ${searchSecs.toFixed(1)}s`;
+    if (maxWithout > 0) {
+        title += `; orange: people without a choice, from ${maxWithout} down to ${last.without_choices}`;
+    }
+    return `
+        <svg class="convergence-chart" viewBox="0 0 ${width} ${height}" preserveAspectRatio="none">
+            <title>${title}</title>
+            ${upperBound > 0 ? `<line x1="0" x2="${width}" y1="${y(upperBound)}" y2="${y(upperBound)}" class="convergence-bound" />` : ''}
+            ${maxWithout > 0 ? `<polyline points="${steps(p => yWithout(p.without_choices))}" class="convergence-without" />` : ''}
+            <polyline points="${steps(p => y(p.choice_score))}" class="convergence-line" />
+        </svg>
+    `;
+}
+
+// Flags searches where many constructions got stuck, and on whom
+function placementNotes(result) {
+    const rate = result.construction_failure_rate || 0;
+    if (rate < 0.1) return [];
+    const names = Object.fromEntries((result.people || []).map(p => [p.id, p.name]));
+    const hardest = Object.entries(result.placement_failures || {})
+        .sort((a, b) => b[1] - a[1])
+        .slice(0, 5)
+        .map(([id, count]) => `${names[id] ?? id} (${count.toLocaleString()})`)
+        .join(', ');
+    return [`${Math.round(rate * 100)}% of attempts couldn't place everyone, most often ${hardest}. ` +
+        'Their avoids or room rules leave few rooms they can go in.'];
+}
+
+// Tells organizers whether more iterations could still help
+function gapNote(result) {
+    if (result.upper_bound <= 0) return 'No choices can be satisfied';
+    if (result.choice_score >= result.upper_bound) return 'Bound reached: no better score exists';
+    const perCategory = Object.entries(result.category_bounds || {})
+        .filter(([, b]) => b.gap > 0)
+        .map(([category, b]) => `${category}: ${b.gap}`)
+        .join(', ');
+    return `Gap ${result.optimality_gap.toFixed(1)}%` + (perCategory ? ` (${perCategory})` : '');
+}
+
+// One line per problem `verify_assignment` found, mostly after hand edits
+function violationText(v, nameOf) {
+    const room = `${v.category} Room ${v.room + 1}`;
+    switch (v.kind) {
+        case 'unplaced': return `${nameOf(v.person)} has no room`;
+        case 'placed_more_than_once': return `${nameOf(v.person)} is in ${v.times} rooms`;
+        case 'unknown_person': return `${room} holds ${v.person}, who isn't in the spreadsheet`;
+        case 'over_capacity': return `${room} has ${v.size} people, over the room size of ${v.max_size}`;
+        case 'wrong_category': return `${nameOf(v.person)} (${v.person_category}) is in ${room}`;
+        case 'hard_avoid': return `${nameOf(v.person)} shares ${room} with ${nameOf(v.avoided)}, whom they avoid`;
+        case 'hard_rule': return `${room}: <code>${v.rule}</code> off by ${v.amount}`;
+        default: return room;
+    }
+}
+
+// Sends a move or swap to the backend and shows the rescored result
+async function editResult(command, args) {
+    try {
+        currentResult = await window.__TAURI_INTERNALS__.invoke(command, {
+            result: currentResult,
+            roomSize: currentRoomSize,
+            options: currentOptions,
+            ...args
+        });
+        displayResults(currentResult);
+    } catch (error) {
+        console.error('Error:', error);
+        alert('Error: ' + error);
+    }
+}
+
+// Moves and swaps that would give someone a choice, best first, each with a button to apply it
+async function showSuggestions(personId) {
+    const panel = document.getElementById('resultsSuggestions');
+    const peopleById = Object.fromEntries(currentResult.people.map(p => [p.id, p]));
+    const nameOf = (id) => peopleById[id]?.name ?? id;
+    try {
+        const suggestions = await window.__TAURI_INTERNALS__.invoke('suggest_fixes', {
+            result: currentResult,
+            personId: personId,
+            roomSize: currentRoomSize,
+            options: currentOptions
+        });
+        const top = suggestions.slice(0, 5);
+        const describe = (s) => {
+            const where = `${s.category} Room ${s.room + 1}`;
+            const action = s.swap_with ? `Swap with ${nameOf(s.swap_with)} in ${where}` : `Move to ${where}`;
+            const effect = [`overall ${s.objective_change >= 0 ? '+' : ''}${s.objective_change}`];
+            if (s.newly_unhappy.length > 0) {
+                effect.push(`leaves ${s.newly_unhappy.map(nameOf).join(', ')} without a choice`);
+            }
+            return `${action} <span class="summary-note">${effect.join(' · ')}</span>`;
+        };
+        panel.style.display = 'block';
+        panel.innerHTML = `
+            <div class="warnings-title">Fixes for ${nameOf(personId)}</div>
+            ${top.length === 0 ? '<p>No single move or swap gives them a choice without breaking a rule.</p>' : `
+                <ul>
+                    ${top.map((s, idx) => `
+                        <li><span>${describe(s)}</span><button data-suggestion="${idx}">Apply</button></li>
+                    `).join('')}
+                </ul>
+            `}
+        `;
+        panel.querySelectorAll('button[data-suggestion]').forEach(button => {
+            button.addEventListener('click', () => {
+                const s = top[parseInt(button.dataset.suggestion)];
+                if (s.swap_with) {
+                    editResult('swap_people', { firstId: s.person, secondId: s.swap_with });
+                } else {
+                    editResult('move_person', { personId: s.person, category: s.category, room: s.room });
+                }
+            });
+        });
+    } catch (error) {
+        console.error('Error:', error);
+        alert('Error: ' + error);
+    }
+}
+
+// Drag someone onto a room to move them there, or onto another person to swap the two
+function enableEditing(detail) {
+    let dragged = null;
+
+    detail.querySelectorAll('.member[data-id]').forEach(member => {
+        member.addEventListener('dragstart', (e) => {
+            dragged = member.dataset.id;
+            e.dataTransfer.effectAllowed = 'move';
+            member.classList.add('dragging');
+        });
+        member.addEventListener('dragend', () => member.classList.remove('dragging'));
+        if (member.classList.contains('unhappy')) {
+            member.addEventListener('click', () => showSuggestions(member.dataset.id));
+        }
+    });
+
+    detail.querySelectorAll('.room[data-category]').forEach(room => {
+        room.addEventListener('dragover', (e) => {
+            if (!dragged) return;
+            e.preventDefault();
+            room.classList.add('drop-target');
+        });
+        room.addEventListener('dragleave', () => room.classList.remove('drop-target'));
+        room.addEventListener('drop', async (e) => {
+            e.preventDefault();
+            room.classList.remove('drop-target');
+            const id = dragged;
+            dragged = null;
+            const onto = e.target.closest('.member[data-id]');
+            if (onto && onto.dataset.id !== id) {
+                await editResult('swap_people', { firstId: id, secondId: onto.dataset.id });
+            } else if (!onto) {
+                await editResult('move_person', {
+                    personId: id,
+                    category: room.dataset.category,
+                    room: parseInt(room.dataset.room)
+                });
+            }
+        });
+    });
+}
+
 function displayResults(result) {
     document.getElementById('progress').style.display = 'none';
+    document.getElementById('resultsSuggestions').style.display = 'none';
     document.getElementById('results').style.display = 'block';
 
     const summary = document.getElementById('resultsSummary');
@@ -166,6 +499,11 @@ function displayResults(result) {
             <span class="summary-label">Choice Satisfaction Score</span>
             <span class="summary-value">${result.choice_score}</span>
         </div>
+        <div class="summary-item">
+            <span class="summary-label">Upper Bound</span>
+            <span class="summary-value">${result.upper_bound}</span>
+            <span class="summary-note">${gapNote(result)}</span>
+        </div>
         <div class="summary-item">
             <span class="summary-label">Room Balance</span>
             <span class="summary-value">${result.imbalance}</span>
@@ -178,23 +516,162 @@ function displayResults(result) {
             <span class="summary-label">Total Rooms</span>
             <span class="summary-value">${result.total_rooms}</span>
         </div>
+        ${result.optimal_categories && result.optimal_categories.length > 0 ? `
+            <div class="summary-item">
+                <span class="summary-label">Proven Optimal</span>
+                <span class="summary-value">${result.proven_optimal ? 'All' : result.optimal_categories.join(', ')}</span>
+            </div>
+        ` : ''}
     `;
 
+    const warnings = document.getElementById('resultsWarnings');
+    const warmStartIssues = [...(result.warm_start_issues || []), ...placementNotes(result)];
+    if ((result.warnings && result.warnings.length > 0) || warmStartIssues.length > 0) {
+        warnings.style.display = 'block';
+        warnings.innerHTML = `
+            <div class="warnings-title">Spreadsheet Warnings</div>
+            <ul>
+                ${(result.warnings || []).map(w => `
+                    <li><span class="cell-ref">${w.sheet}!${w.column}${w.row}</span> ${w.message}</li>
+                `).join('')}
+                ${warmStartIssues.map(issue => `<li>${issue}</li>`).join('')}
+            </ul>
+        `;
+    } else {
+        warnings.style.display = 'none';
+        warnings.innerHTML = '';
+    }
+
+    // Rooms hold person IDs; show display names and any extra attributes
+    const peopleById = Object.fromEntries(result.people.map(p => [p.id, p]));
+    const nameOf = (id) => peopleById[id]?.name ?? id;
+
+    const broken = document.getElementById('resultsBroken');
+    if (result.violations && result.violations.length > 0) {
+        broken.style.display = 'block';
+        broken.innerHTML = `
+            <div class="warnings-title">Broken Rules</div>
+            <ul>
+                ${result.violations.map(v => `<li>${violationText(v, nameOf)}</li>`).join('')}
+            </ul>
+        `;
+    } else {
+        broken.style.display = 'none';
+        broken.innerHTML = '';
+    }
+
+    const violations = document.getElementById('resultsViolations');
+    if (result.rule_violations && result.rule_violations.length > 0) {
+        violations.style.display = 'block';
+        violations.innerHTML = `
+            <div class="warnings-title">Room Rules Not Met (penalty ${result.soft_penalty})</div>
+            <ul>
+                ${result.rule_violations.map(v => `
+                    <li>${v.category} Room ${v.room + 1}: <code>${v.rule}</code> off by ${v.amount}</li>
+                `).join('')}
+            </ul>
+        `;
+    } else {
+        violations.style.display = 'none';
+        violations.innerHTML = '';
+    }
+
+    const softAvoids = document.getElementById('resultsSoftAvoids');
+    if (result.soft_avoid_violations && result.soft_avoid_violations.length > 0) {
+        softAvoids.style.display = 'block';
+        softAvoids.innerHTML = `
+            <div class="warnings-title">Prefer-Not Pairs Sharing a Room</div>
+            <ul>
+                ${result.soft_avoid_violations.map(v => `
+                    <li>${v.category} Room ${v.room + 1}: ${nameOf(v.person)} preferred not to room with ${nameOf(v.avoided)}</li>
+                `).join('')}
+            </ul>
+        `;
+    } else {
+        softAvoids.style.display = 'none';
+        softAvoids.innerHTML = '';
+    }
+    const attributeKeys = [...new Set(result.people.flatMap(p => Object.keys(p.attributes || {})))];
+
+    // e.g. "Choice 1 · 2 mutual", or why someone is unhappy
+    const satisfaction = (stats) => {
+        if (!stats || stats.choices_made === 0) return '';
+        if (stats.best_choice_rank == null) return 'No choices in room';
+        const parts = [`Choice ${stats.best_choice_rank}`];
+        if (stats.mutual_matches > 0) parts.push(`${stats.mutual_matches} mutual`);
+        if (stats.chosen_by > 0) parts.push(`chosen by ${stats.chosen_by}`);
+        return parts.join(' · ');
+    };
+
+    const renderMember = (id) => {
+        const person = peopleById[id];
+        if (!person) return `<span class="member">${id}</span>`;
+        const stats = result.person_stats?.[id];
+        const unhappy = stats && stats.choices_made > 0 && stats.choices_received === 0;
+        const attrs = Object.entries(person.attributes || {})
+            .map(([key, value]) => `${key}: ${value}`)
+            .join(' · ');
+        const happiness = satisfaction(stats);
+        return `
+            <span class="member${unhappy ? ' unhappy' : ''}" title="${id}" data-id="${id}" draggable="true">
+                ${person.name}
+                ${happiness ? `<span class="member-satisfaction">${happiness}</span>` : ''}
+                ${attrs ? `<span class="member-attrs">${attrs}</span>` : ''}
+            </span>
+        `;
+    };
+
+    const categoryNote = (category) => {
+        const stats = result.category_stats?.[category];
+        if (!stats) return '';
+        return `Score ${stats.choice_score} · imbalance ${stats.imbalance} · ` +
+            `${stats.without_choices} without choices · ${stats.mutual_pairs} mutual pairs · ` +
+            (stats.kept_starting_rooms ? 'no improvement on the starting rooms' : `found by ${strategyName(stats.strategy)}`) +
+            (stopReasonNote(stats.stop_reason) ? ` · ${stopReasonNote(stats.stop_reason)}` : '');
+    };
+
     const detail = document.getElementById('resultsDetail');
     detail.innerHTML = Object.entries(result.rooms_by_category)
         .map(([category, rooms]) => `
             <div class="room-group">
-                <div class="room-category">${category} Rooms</div>
+                <div class="room-category">
+                    ${category} Rooms
+                    <span class="room-category-stats">${categoryNote(category)}</span>
+                    ${convergenceChart(result.convergence?.[category], result.search_secs,
+                                       result.category_bounds?.[category]?.upper_bound ?? 0)}
+                </div>
                 ${rooms.map((room, idx) => `
-                    <div class="room">
+                    <div class="room" data-category="${category}" data-room="${idx}">
                         <div class="room-title">Room ${idx + 1} - ${room.length} people</div>
                         <div class="room-members">
-                            ${room.map(name => `<span class="member">${name}</span>`).join('')}
+                            ${room.map(renderMember).join('')}
                         </div>
                     </div>
                 `).join('')}
+                <div class="room new-room" data-category="${category}" data-room="${rooms.length}">
+                    Drop here for a new room
+                </div>
             </div>
         `).join('');
+    enableEditing(detail);
+
+    // Rebuilt after every edit; keep whatever was already ticked
+    const pdfAttributes = document.getElementById('pdfAttributes');
+    const ticked = new Set(Array.from(
+        pdfAttributes.querySelectorAll('input[type="checkbox"]:checked')
+    ).map(box => box.value));
+    if (attributeKeys.length > 0) {
+        pdfAttributes.style.display = 'block';
+        pdfAttributes.innerHTML = `
+            <div class="pdf-attributes-title">Include in PDF room listings</div>
+            ${attributeKeys.map(key => `
+                <label><input type="checkbox" value="${key}" ${ticked.has(key) ? 'checked' : ''} /> ${key}</label>
+            `).join('')}
+        `;
+    } else {
+        pdfAttributes.style.display = 'none';
+        pdfAttributes.innerHTML = '';
+    }
 }
 
 
@@ -209,12 +686,7 @@ function handleRunAgain() {
     // Scroll to top
     window.scrollTo({ top: 0, behavior: 'smooth' });
     
-    // Increase iterations by 50%
-    const currentIterations = parseInt(document.getElementById('iterations').value);
-    const newIterations = Math.floor(currentIterations * 1.5);
-    document.getElementById('iterations').value = newIterations;
-    
-    setTimeout(() => {
-        alert(`Iterations increased to ${newIterations}. Adjust settings and click "Solve Room Assignments" to try again.`);
-    }, 100);
+    // The next solve can pick up where this one left off
+    document.getElementById('warmStartOption').style.display = 'block';
+    document.getElementById('warmStart').checked = true;
 }
\ No newline at end of file